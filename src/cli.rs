@@ -1,18 +1,105 @@
-use clap::{ArgGroup, Parser, Subcommand};
 use super::commands as cmd;
+use super::commands::merge::StrategyOption;
+use super::utils::diff::{DiffAlgorithm, DiffOptions};
+use super::utils::util;
+use clap::{ArgGroup, Args, Parser, Subcommand};
 /// Rust实现的简易版本的Git，用于学习Rust语言
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// 在<path>中运行，如同在该目录下启动mit；可以指定多次，后面的相对于前面的
+    #[clap(short = 'C', value_name = "path")]
+    change_dir: Vec<String>,
+
+    /// 仓库目录（即 .mit），也可以通过环境变量 MIT_DIR 指定
+    #[clap(long, value_name = "path")]
+    mit_dir: Option<String>,
+
+    /// 工作区目录，也可以通过环境变量 MIT_WORK_TREE 指定
+    #[clap(long, value_name = "path")]
+    work_tree: Option<String>,
+
     /// The subcommand to run.
     #[clap(subcommand)]
     command: Command,
 }
+/// stash的子命令，`<stash>` 为 `stash@{n}` 或 `n`，默认为最新的 `stash@{0}`
+#[derive(Subcommand)]
+enum StashCommand {
+    /// 保存当前的更改，并恢复到HEAD
+    Push {
+        /// 同时保存并清除未跟踪的文件
+        #[clap(short = 'u', long)]
+        include_untracked: bool,
+
+        /// stash的描述
+        #[clap(short, long)]
+        message: Option<String>,
+    },
+    /// 应用stash并从列表中删除
+    Pop { stash: Option<String> },
+    /// 应用stash，保留在列表中
+    Apply { stash: Option<String> },
+    /// 列出所有stash
+    List,
+    /// 显示stash中的文件变更
+    Show { stash: Option<String> },
+    /// 从列表中删除stash
+    Drop { stash: Option<String> },
+}
+/// diff相关的公共参数
+#[derive(Args, Debug, Clone)]
+pub struct DiffArgs {
+    /// diff算法，默认读取配置 diff.algorithm
+    #[clap(long, value_enum)]
+    diff_algorithm: Option<DiffAlgorithm>,
+
+    /// 忽略所有空白
+    #[clap(short = 'w', long)]
+    ignore_all_space: bool,
+
+    /// 忽略空白数量的变化
+    #[clap(short = 'b', long)]
+    ignore_space_change: bool,
+
+    /// 忽略只包含空行的更改
+    #[clap(long)]
+    ignore_blank_lines: bool,
+}
+
+impl DiffArgs {
+    fn to_options(&self) -> DiffOptions {
+        let mut options = DiffOptions::from_config();
+        if let Some(algorithm) = self.diff_algorithm {
+            options.algorithm = algorithm;
+        }
+        options.ignore_all_space = self.ignore_all_space;
+        options.ignore_space_change = self.ignore_space_change;
+        options.ignore_blank_lines = self.ignore_blank_lines;
+        options
+    }
+}
+
 /// @see <a href="https://juejin.cn/post/7242623208825110586">Rust Clap库学习 - 掘金</a>
 #[derive(Subcommand)]
 enum Command {
     /// 初始化仓库
-    Init,
+    Init {
+        /// 仓库目录，默认为当前目录
+        directory: Option<String>,
+
+        /// 初始分支名，默认为配置 init.defaultBranch，否则为master
+        #[clap(short = 'b', long)]
+        initial_branch: Option<String>,
+
+        /// 模板目录，其中的文件会被复制到 .mit 中
+        #[clap(long)]
+        template: Option<String>,
+
+        /// 创建裸仓库：没有工作区，仓库文件直接位于目录中
+        #[clap(long)]
+        bare: bool,
+    },
     /// 添加文件到暂存区
     /// @see <a href="https://juejin.cn/post/7053831273277554696">git add .，git add -A，git add -u，git add * 的区别与联系</a>
     Add {
@@ -52,19 +139,40 @@ enum Command {
     /// log 现实提交历史
     #[clap(group = ArgGroup::new("sub").required(false))]
     Log {
+        /// 要显示的提交范围，e.g. `main..feature`、`^main`，默认HEAD
+        revs: Vec<String>,
+
         #[clap(short = 'A', long)]
         all: bool,
 
         #[clap(short, long)]
         number: Option<usize>,
+
+        /// 显示每个提交中变更的文件及其状态
+        #[clap(long)]
+        name_status: bool,
+    },
+    /// 按时间倒序列出可达的提交，支持 `A..B`、`A...B`、`^A`
+    RevList {
+        /// 起点与排除项
+        #[clap(required = true)]
+        revs: Vec<String>,
+
+        /// 只输出提交数量
+        #[clap(long)]
+        count: bool,
+
+        /// 最多输出的提交数量
+        #[clap(short = 'n', long)]
+        max_count: Option<usize>,
     },
     /// branch
     Branch {
-        /// 新分支名
+        /// 新分支名；-m/-c 时为原分支名（只指定一个时为当前分支的新名称）
         #[clap(group = "sub")]
         new_branch: Option<String>,
 
-        /// 基于某个commit创建分支
+        /// 基于某个commit创建分支；-m/-c 时为新分支名
         #[clap(requires = "new_branch")]
         commit_hash: Option<String>,
 
@@ -72,19 +180,103 @@ enum Command {
         #[clap(short, long, action, group = "sub", default_value = "true")]
         list: bool,
 
-        /// 删除制定分支，不能删除当前所在分支
-        #[clap(short = 'D', long, group = "sub")]
+        /// 删除指定分支，分支需已合并到HEAD，不能删除当前所在分支
+        #[clap(short, long, group = "sub")]
         delete: Option<String>,
 
+        /// 强制删除指定分支，即使没有合并
+        #[clap(short = 'D', group = "sub")]
+        force_delete: Option<String>,
+
+        /// 重命名分支
+        #[clap(short = 'm', long = "move", requires = "new_branch", conflicts_with = "copy")]
+        rename: bool,
+
+        /// 重命名分支，即使新分支名已存在
+        #[clap(short = 'M', requires = "new_branch", conflicts_with_all(["rename", "copy"]))]
+        force_rename: bool,
+
+        /// 复制分支
+        #[clap(short, long, requires = "new_branch")]
+        copy: bool,
+
         /// 显示当前分支
         #[clap(long, action, group = "sub")]
         show_current: bool,
+
+        /// 只列出已合并到commit（默认HEAD）的分支
+        #[clap(long, value_name = "COMMIT", num_args = 0..=1, default_missing_value = "HEAD")]
+        merged: Option<String>,
+
+        /// 只列出没有合并到commit（默认HEAD）的分支
+        #[clap(long, value_name = "COMMIT", num_args = 0..=1, default_missing_value = "HEAD", conflicts_with = "merged")]
+        no_merged: Option<String>,
+
+        /// 显示每个分支的commit和相对上游分支的ahead/behind，-vv 同时显示上游分支名
+        #[clap(short, long, action = clap::ArgAction::Count)]
+        verbose: u8,
+
+        /// 排序：refname | committerdate | objectname，'-'开头时倒序
+        #[clap(long, value_name = "KEY")]
+        sort: Option<String>,
+
+        /// 每个分支的输出格式，e.g. '%(refname:short) %(upstream:track)'
+        #[clap(long)]
+        format: Option<String>,
+
+        /// 设置分支（默认当前分支）跟踪的上游分支
+        #[clap(short = 'u', long = "set-upstream-to", value_name = "UPSTREAM")]
+        set_upstream: Option<String>,
+
+        /// 取消分支（默认当前分支）的上游分支
+        #[clap(long, conflicts_with = "set_upstream")]
+        unset_upstream: bool,
     },
 
+    /// 创建、列出、删除、验证标签
+    Tag {
+        /// 标签名，不指定时列出所有标签
+        name: Option<String>,
+
+        /// 标签指向的commit，默认为HEAD
+        target: Option<String>,
+
+        /// 创建附注标签，需要同时指定 -m
+        #[clap(short, long)]
+        annotate: bool,
+
+        /// 附注标签的说明，指定时创建附注标签
+        #[clap(short, long)]
+        message: Option<String>,
+
+        /// 替换已存在的标签
+        #[clap(short, long)]
+        force: bool,
+
+        /// 删除标签
+        #[clap(short, long, requires = "name", conflicts_with_all(["target", "annotate", "message", "force", "verify"]))]
+        delete: bool,
+
+        /// 验证附注标签，并显示其内容
+        #[clap(short, long, requires = "name", conflicts_with_all(["target", "annotate", "message", "force"]))]
+        verify: bool,
+    },
+    /// 将tag（--all时包括分支）打包到packed-refs
+    PackRefs {
+        /// 同时打包分支
+        #[clap(long)]
+        all: bool,
+    },
+    /// 显示HEAD或分支的更新记录（reflog）
+    Reflog {
+        /// ref名，e.g. HEAD、master、stash，默认为HEAD
+        #[clap(name = "ref")]
+        ref_name: Option<String>,
+    },
     /// 切换分支
     Switch {
         /// 要切换的分支
-        #[clap(required_unless_present("create"))]
+        #[clap(required_unless_present_any(["create", "orphan"]))]
         branch: Option<String>,
 
         /// 创建并切换到新分支
@@ -94,6 +286,32 @@ enum Command {
         /// 是否允许切换到commit
         #[clap(long, short, action, default_value = "false", group = "sub")]
         detach: bool,
+
+        /// 切换到新的没有任何提交的分支，并清空暂存区和已跟踪的文件
+        #[clap(long, group = "sub", conflicts_with = "branch")]
+        orphan: Option<String>,
+    },
+    /// 将当前分支移动到指定commit，并重置暂存区和工作区
+    #[clap(group = ArgGroup::new("mode").required(false))]
+    Reset {
+        /// 目标commit，默认为HEAD
+        target: Option<String>,
+
+        /// 只移动分支，保留暂存区和工作区
+        #[clap(long, group = "mode")]
+        soft: bool,
+
+        /// 重置暂存区，保留工作区（默认）
+        #[clap(long, group = "mode")]
+        mixed: bool,
+
+        /// 重置暂存区和工作区，丢弃所有更改
+        #[clap(long, group = "mode")]
+        hard: bool,
+
+        /// 只在暂存区中恢复这些文件，不移动分支
+        #[clap(last = true)]
+        paths: Vec<String>,
     },
     /// restore
     Restore {
@@ -116,15 +334,182 @@ enum Command {
     /// merge
     Merge {
         /// 要合并的分支
-        #[clap(required = true)]
-        branch: String,
+        #[clap(required_unless_present_any(["abort", "continue_merge"]))]
+        branch: Option<String>,
+
+        /// 放弃合并，恢复到合并前的状态
+        #[clap(long, conflicts_with_all(["branch", "continue_merge"]))]
+        abort: bool,
+
+        /// 解决冲突后完成合并
+        #[clap(long = "continue", conflicts_with = "branch")]
+        continue_merge: bool,
+
+        /// 总是生成merge commit，即使可以fast forward
+        #[clap(long, conflicts_with_all(["ff_only", "squash"]))]
+        no_ff: bool,
+
+        /// 只允许fast forward
+        #[clap(long, conflicts_with = "squash")]
+        ff_only: bool,
+
+        /// 将合并结果写入暂存区，但不提交
+        #[clap(long)]
+        squash: bool,
+
+        /// merge commit的提交信息
+        #[clap(short, long)]
+        message: Option<String>,
+
+        /// 冲突时自动选择某一方的内容：ours | theirs
+        #[clap(short = 'X', long, value_enum)]
+        strategy_option: Option<StrategyOption>,
+    },
+    /// 将已有commit的更改应用到当前分支
+    CherryPick {
+        /// 要应用的commit，支持范围 e.g. `main..feature`
+        #[clap(required_unless_present_any(["continue_pick", "skip", "abort"]))]
+        commits: Vec<String>,
+
+        /// 只应用到工作区和暂存区，不提交
+        #[clap(short, long)]
+        no_commit: bool,
+
+        /// 在提交信息中追加 "(cherry picked from commit ...)"
+        #[clap(short = 'x')]
+        record_origin: bool,
+
+        /// 解决冲突后继续
+        #[clap(long = "continue", conflicts_with_all(["commits", "skip", "abort"]))]
+        continue_pick: bool,
+
+        /// 跳过当前commit
+        #[clap(long, conflicts_with_all(["commits", "abort"]))]
+        skip: bool,
+
+        /// 放弃cherry-pick，恢复到开始前的状态
+        #[clap(long, conflicts_with = "commits")]
+        abort: bool,
+    },
+    /// 撤销已有commit的更改，并生成新的commit
+    Revert {
+        /// 要撤销的commit，支持范围 e.g. `HEAD~3..`
+        #[clap(required_unless_present_any(["continue_revert", "skip", "abort"]))]
+        commits: Vec<String>,
+
+        /// 撤销merge commit时，以第几个parent(从1开始)为主线
+        #[clap(short, long)]
+        mainline: Option<usize>,
+
+        /// 只应用到工作区和暂存区，不提交
+        #[clap(short, long)]
+        no_commit: bool,
+
+        /// 解决冲突后继续
+        #[clap(long = "continue", conflicts_with_all(["commits", "skip", "abort"]))]
+        continue_revert: bool,
+
+        /// 跳过当前commit
+        #[clap(long, conflicts_with_all(["commits", "abort"]))]
+        skip: bool,
+
+        /// 放弃revert，恢复到开始前的状态
+        #[clap(long, conflicts_with = "commits")]
+        abort: bool,
+    },
+    /// 将当前分支的commit重新应用到另一个基础上
+    Rebase {
+        /// 新的基础，当前分支在其之后的commit会被依次应用
+        #[clap(required_unless_present_any(["continue_rebase", "skip", "abort"]))]
+        upstream: Option<String>,
+
+        /// 从文件读取操作列表，每行为 `pick|reword|squash|fixup|drop <commit> [text]`，reword的text为新的提交信息
+        #[clap(long = "todo", value_name = "FILE", conflicts_with_all(["continue_rebase", "skip", "abort"]))]
+        todo_file: Option<String>,
+
+        /// 解决冲突后继续
+        #[clap(long = "continue", conflicts_with_all(["upstream", "skip", "abort"]))]
+        continue_rebase: bool,
+
+        /// 跳过当前commit
+        #[clap(long, conflicts_with_all(["upstream", "abort"]))]
+        skip: bool,
+
+        /// 放弃rebase，恢复到开始前的状态
+        #[clap(long, conflicts_with = "upstream")]
+        abort: bool,
+    },
+    /// 暂存工作区和暂存区的更改，默认为push
+    Stash {
+        #[clap(subcommand)]
+        command: Option<StashCommand>,
+    },
+    /// 查找两个commit的最近公共祖先
+    MergeBase {
+        /// 要比较的两个commit
+        #[clap(required = true, num_args = 2)]
+        commits: Vec<String>,
+
+        /// 输出所有最近公共祖先（criss-cross合并时可能有多个）
+        #[clap(short, long)]
+        all: bool,
+
+        /// 检查第一个commit是否是第二个的祖先，结果通过退出码表示
+        #[clap(long, conflicts_with = "all")]
+        is_ancestor: bool,
+    },
+    /// 显示工作区与暂存区（或暂存区与HEAD）的差异
+    Diff {
+        /// 要比较的文件
+        paths: Vec<String>,
+
+        /// 比较暂存区与HEAD
+        #[clap(long, visible_alias = "staged")]
+        cached: bool,
+
+        #[clap(flatten)]
+        diff_args: DiffArgs,
+    },
+    /// 显示对象：commit(及其diff)、tree、blob，或 <rev>:<path> 对应的文件
+    Show {
+        /// 要显示的对象，默认HEAD
+        revs: Vec<String>,
+
+        #[clap(flatten)]
+        diff_args: DiffArgs,
+    },
+    /// 读写仓库配置
+    Config {
+        /// 配置项，e.g. diff.algorithm
+        key: Option<String>,
+
+        /// 配置值，为空时打印当前值
+        value: Option<String>,
+
+        /// 删除配置项
+        #[clap(long, requires = "key")]
+        unset: bool,
+
+        /// 列出所有配置
+        #[clap(short, long)]
+        list: bool,
     },
 }
 pub fn handle_command() {
     let cli = Cli::parse();
+    for dir in &cli.change_dir {
+        if let Err(err) = std::env::set_current_dir(dir) {
+            return println!("fatal: cannot change to '{}': {}", dir, err);
+        }
+    }
+    let init = matches!(cli.command, Command::Init { .. });
+    if let Err(err) = util::setup_repo_location(cli.mit_dir, cli.work_tree, init) {
+        return println!("fatal: {}", err);
+    }
     match cli.command {
-        Command::Init => {
-            cmd::init().expect("初始化失败");
+        Command::Init { directory, initial_branch, template, bare } => {
+            let options = cmd::init::InitOptions { directory, initial_branch, template, bare };
+            cmd::init(options).expect("初始化失败");
         }
         Command::Add { files, all, update } => {
             cmd::add(files, all, update);
@@ -138,14 +523,69 @@ pub fn handle_command() {
         Command::Status => {
             cmd::status();
         }
-        Command::Log { all, number } => {
-            cmd::log(all, number);
+        Command::Log { revs, all, number, name_status } => {
+            cmd::log(revs, all, number, name_status);
+        }
+        Command::RevList { revs, count, max_count } => {
+            cmd::rev_list(revs, count, max_count);
+        }
+        Command::Branch {
+            list,
+            delete,
+            force_delete,
+            new_branch,
+            commit_hash,
+            rename,
+            force_rename,
+            copy,
+            show_current,
+            merged,
+            no_merged,
+            verbose,
+            sort,
+            format,
+            set_upstream,
+            unset_upstream,
+        } => {
+            let options = cmd::branch::BranchOptions {
+                list,
+                force: force_delete.is_some() || force_rename,
+                delete: delete.or(force_delete),
+                rename: rename || force_rename,
+                copy,
+                show_current,
+                merged,
+                no_merged,
+                verbose,
+                sort,
+                format,
+                set_upstream,
+                unset_upstream,
+            };
+            cmd::branch(new_branch, commit_hash, options);
         }
-        Command::Branch { list, delete, new_branch, commit_hash, show_current } => {
-            cmd::branch(new_branch, commit_hash, list, delete, show_current);
+        Command::Tag { name, target, annotate, message, force, delete, verify } => {
+            cmd::tag(name, target, annotate, message, force, delete, verify);
         }
-        Command::Switch { branch, create, detach } => {
-            cmd::switch(branch, create, detach);
+        Command::PackRefs { all } => {
+            cmd::pack_refs(all);
+        }
+        Command::Reflog { ref_name } => {
+            cmd::reflog(ref_name);
+        }
+        Command::Switch { branch, create, detach, orphan } => match orphan {
+            Some(orphan) => cmd::switch::switch_orphan(orphan),
+            None => cmd::switch(branch, create, detach),
+        },
+        Command::Reset { target, soft, mixed: _, hard, paths } => {
+            let mode = if soft {
+                cmd::reset::ResetMode::Soft
+            } else if hard {
+                cmd::reset::ResetMode::Hard
+            } else {
+                cmd::reset::ResetMode::Mixed
+            };
+            cmd::reset(target, mode, paths);
         }
         Command::Restore { path, source, mut worktree, staged } => {
             // 未指定stage和worktree时，默认操作worktree
@@ -160,8 +600,56 @@ pub fn handle_command() {
             */
             cmd::restore(path, source, worktree, staged);
         }
-        Command::Merge { branch } => {
-            cmd::merge(branch);
+        Command::Merge {
+            branch,
+            abort,
+            continue_merge,
+            no_ff,
+            ff_only,
+            squash,
+            message,
+            strategy_option,
+        } => {
+            let options = cmd::merge::MergeOptions { no_ff, ff_only, squash, message, strategy_option };
+            cmd::merge(branch, abort, continue_merge, options);
+        }
+        Command::CherryPick {
+            commits,
+            no_commit,
+            record_origin,
+            continue_pick,
+            skip,
+            abort,
+        } => {
+            cmd::cherry_pick(commits, no_commit, record_origin, continue_pick, skip, abort);
+        }
+        Command::Revert { commits, mainline, no_commit, continue_revert, skip, abort } => {
+            cmd::revert(commits, mainline, no_commit, continue_revert, skip, abort);
+        }
+        Command::Rebase { upstream, todo_file, continue_rebase, skip, abort } => {
+            cmd::rebase(upstream, todo_file, continue_rebase, skip, abort);
+        }
+        Command::Stash { command } => {
+            match command.unwrap_or(StashCommand::Push { include_untracked: false, message: None }) {
+                StashCommand::Push { include_untracked, message } => cmd::stash::push(message, include_untracked),
+                StashCommand::Pop { stash } => cmd::stash::pop(stash),
+                StashCommand::Apply { stash } => cmd::stash::apply(stash),
+                StashCommand::List => cmd::stash::list(),
+                StashCommand::Show { stash } => cmd::stash::show(stash),
+                StashCommand::Drop { stash } => cmd::stash::drop(stash),
+            }
+        }
+        Command::MergeBase { commits, all, is_ancestor } => {
+            cmd::merge_base(commits, all, is_ancestor);
+        }
+        Command::Diff { paths, cached, diff_args } => {
+            cmd::diff(paths, cached, diff_args.to_options());
+        }
+        Command::Show { revs, diff_args } => {
+            cmd::show(revs, diff_args.to_options());
+        }
+        Command::Config { key, value, unset, list } => {
+            cmd::config(key, value, unset, list);
         }
     }
 }
@@ -11,6 +11,9 @@ use crate::utils::util;
 /// add是对index的操作，不会对工作区产生影响
 pub fn add(raw_paths: Vec<String>, all: bool, mut update: bool) {
     util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
 
     let mut paths: Vec<PathBuf> = raw_paths.into_iter().map(PathBuf::from).collect();
     if all || update {
@@ -65,11 +68,12 @@ fn add_a_file(file: &Path, index: &mut Index) {
             index.add(file.to_path_buf(), FileMetaData::new(&blob, file));
             println!("add(stage): {}", rel_path.display());
         } else {
-            //文件已被跟踪，可能被修改
-            if index.is_modified(file) {
+            //文件已被跟踪，可能被修改；有冲突的文件add即表示已解决，需要清除stages
+            let conflicted = index.get(file).is_some_and(|data| data.stages.is_some());
+            if index.is_modified(file) || conflicted {
                 //文件被修改，但不一定内容更改
                 let blob = Blob::new(util::read_workfile(file)); //到这一步才创建blob是为了优化
-                if !index.verify_hash(file, &blob.get_hash()) {
+                if !index.verify_hash(file, &blob.get_hash()) || conflicted {
                     //比较hash 确认内容更改
                     index.update(file.to_path_buf(), FileMetaData::new(&blob, file));
                     println!("add(modified): {}", rel_path.display());
@@ -1,51 +1,107 @@
 use colored::Colorize;
 
 use crate::{
+    commands::{merge_base::is_ancestor, rev_list},
     models::*,
-    utils::{store, util},
+    utils::{
+        config::{self, Config},
+        revision,
+    },
 };
 
 // branch error
 enum BranchErr {
     BranchExist,
     InvalidObject,
+    InvalidName,
 
     BranchNoExist,
     BranchCheckedOut,
+    NotMerged,
+    HeadDetached,
 }
-// 从分支名、commit hash中搜索commit
-fn search_hash(commit_hash: Hash) -> Option<Hash> {
-    // 分支名
-    if head::list_local_branches().contains(&commit_hash) {
-        let commit_hash = head::get_branch_head(&commit_hash);
-        return Some(commit_hash);
-    }
-    // commit hash
-    let store = store::Store::new();
-    store.search(&commit_hash)
+
+/// branch的可选行为
+#[derive(Debug, Clone, Default)]
+pub struct BranchOptions {
+    /// 列出分支
+    pub list: bool,
+    /// 删除分支（`-d`），需要已合并到HEAD
+    pub delete: Option<String>,
+    /// `-D`、`-M`：删除未合并的分支，或重命名时覆盖已存在的分支
+    pub force: bool,
+    /// `-m`：重命名分支
+    pub rename: bool,
+    /// `-c`：复制分支
+    pub copy: bool,
+    /// 显示当前分支
+    pub show_current: bool,
+    /// `--merged`：只列出已合并到该commit的分支
+    pub merged: Option<String>,
+    /// `--no-merged`：只列出未合并到该commit的分支
+    pub no_merged: Option<String>,
+    /// `-v`、`-vv`
+    pub verbose: u8,
+    /// `--sort`：refname | committerdate | objectname，'-'开头时倒序
+    pub sort: Option<String>,
+    /// `--format`，e.g. `%(refname:short) %(upstream:track)`
+    pub format: Option<String>,
+    /// `-u`：设置上游分支
+    pub set_upstream: Option<String>,
+    /// `--unset-upstream`
+    pub unset_upstream: bool,
 }
 
-fn create_branch(branch_name: String, _base_commit: Hash) -> Result<(), BranchErr> {
-    // 找到正确的base_commit_hash
-    let base_commit = search_hash(_base_commit.clone());
-    if base_commit.is_none() || util::check_object_type(base_commit.clone().unwrap()) != util::ObjectType::Commit {
-        println!("fatal: 非法的 commit: '{}'", _base_commit);
-        return Err(BranchErr::InvalidObject);
+fn check_branch_name(branch_name: &str) -> Result<(), BranchErr> {
+    if branch_name.starts_with('-') || branch_name == "HEAD" || !head::check_ref_name(branch_name) {
+        println!("fatal: '{}' 不是合法的分支名", branch_name);
+        return Err(BranchErr::InvalidName);
     }
+    Ok(())
+}
 
-    let base_commit = Commit::load(&base_commit.unwrap());
-
-    let exist_branches = head::list_local_branches();
-    if exist_branches.contains(&branch_name) {
+/// 新分支名不能与已有分支冲突；ignore为重命名时的原分支
+fn check_new_branch(branch_name: &String, force: bool, ignore: Option<&String>) -> Result<(), BranchErr> {
+    let exist_branches: Vec<String> = head::list_local_branches().into_iter().filter(|b| Some(b) != ignore).collect();
+    if exist_branches.contains(branch_name) && !force {
         println!("fatal: 分支 '{}' 已存在", branch_name);
         return Err(BranchErr::BranchExist);
     }
+    // `a` 与 `a/b` 不能同时存在：refs/heads/a 不能既是文件又是目录
+    if let Some(exist) = exist_branches.iter().find(|exist| {
+        branch_name.starts_with(&format!("{}/", exist)) || exist.starts_with(&format!("{}/", branch_name))
+    }) {
+        println!("fatal: 无法创建分支 '{}'：与已存在的分支 '{}' 冲突", branch_name, exist);
+        return Err(BranchErr::BranchExist);
+    }
+    Ok(())
+}
 
-    head::update_branch(&branch_name, &base_commit.get_hash());
+/// 检查新分支名是否合法且不与已有分支冲突，不合法时打印原因
+pub fn check_new_branch_name(branch_name: &String) -> bool {
+    check_branch_name(branch_name).is_ok() && check_new_branch(branch_name, false, None).is_ok()
+}
+
+fn create_branch(branch_name: String, _base_commit: Hash) -> Result<(), BranchErr> {
+    check_branch_name(&branch_name)?;
+    // 找到正确的base_commit_hash
+    let base_commit = match revision::resolve_commit(&_base_commit) {
+        Ok(commit) => commit,
+        Err(err) => {
+            println!("{}", err);
+            return Err(BranchErr::InvalidObject);
+        }
+    };
+
+    let base_commit = Commit::load(&base_commit);
+    check_new_branch(&branch_name, false, None)?;
+
+    head::update_branch(&branch_name, &base_commit.get_hash(), &format!("branch: Created from {}", _base_commit));
     Ok(())
 }
 
-fn delete_branch(branch_name: String) -> Result<(), BranchErr> {
+/** 删除分支；未强制时，分支上的commit必须都能从HEAD到达 */
+fn delete_branch(branch_name: String, force: bool) -> Result<(), BranchErr> {
     let branches = head::list_local_branches();
     if !branches.contains(&branch_name) {
         println!("error: 分支 '{}' 不存在", branch_name);
@@ -62,7 +118,59 @@ fn delete_branch(branch_name: String) -> Result<(), BranchErr> {
         return Err(BranchErr::BranchCheckedOut);
     }
 
+    let branch_commit = head::get_branch_head(&branch_name);
+    let head_commit = head::current_head_commit();
+    if !force && (head_commit.is_empty() || !is_ancestor(&branch_commit, &head_commit)) {
+        println!("error: 分支 '{}' 没有完全合并", branch_name);
+        println!("hint: 如果确定要删除，请使用 'mit branch -D {}'", branch_name);
+        return Err(BranchErr::NotMerged);
+    }
+
     head::delete_branch(&branch_name); // 删除refs/heads/branch_name，不删除任何commit
+    let mut config = Config::load();
+    if config.remove_section(&format!("branch.{}", branch_name)) {
+        config.save();
+    }
+    println!("已删除分支 {} (was {})", branch_name, &branch_commit[..7.min(branch_commit.len())]);
+    Ok(())
+}
+
+/** 重命名（copy时复制）分支，old_name为空时为当前分支；分支的配置（branch.<name>）也一并移动或复制 */
+fn move_branch(old_name: Option<String>, new_name: String, force: bool, copy: bool) -> Result<(), BranchErr> {
+    let old_name = match old_name {
+        Some(name) => name,
+        None => match head::current_head() {
+            head::Head::Branch(name) => name,
+            head::Head::Detached(_) => {
+                println!("fatal: 当前不在任何分支上，无法{}", if copy { "复制" } else { "重命名" });
+                return Err(BranchErr::HeadDetached);
+            }
+        },
+    };
+    if !head::list_local_branches().contains(&old_name) {
+        println!("error: 分支 '{}' 不存在", old_name);
+        return Err(BranchErr::BranchNoExist);
+    }
+    check_branch_name(&new_name)?;
+    if old_name == new_name {
+        return Ok(());
+    }
+    if matches!(head::current_head(), head::Head::Branch(current) if current == new_name) && force {
+        println!("fatal: 不能覆盖当前所在分支 '{}'", new_name);
+        return Err(BranchErr::BranchCheckedOut);
+    }
+    check_new_branch(&new_name, force, Some(&old_name))?;
+
+    let (old_ref, new_ref) = (format!("refs/heads/{}", old_name), format!("refs/heads/{}", new_name));
+    let mut config = Config::load();
+    config.copy_section(&format!("branch.{}", old_name), &format!("branch.{}", new_name));
+    if copy {
+        head::copy_branch(&old_name, &new_name, &format!("Branch: copied {} to {}", old_ref, new_ref));
+    } else {
+        head::rename_branch(&old_name, &new_name, &format!("Branch: renamed {} to {}", old_ref, new_ref));
+        config.remove_section(&format!("branch.{}", old_name));
+    }
+    config.save();
     Ok(())
 }
 
@@ -74,44 +182,252 @@ fn show_current_branch() {
     }
 }
 
-fn list_branches() {
-    println!("list_branches");
-    let branches = head::list_local_branches();
-    match head::current_head() {
-        head::Head::Branch(branch_name) => {
-            println!("* {}", branch_name.green());
-            for branch in branches {
-                if branch != branch_name {
-                    println!("  {}", branch);
-                }
+/// 分支跟踪的上游分支，即配置 `branch.<name>.merge`
+pub fn upstream(branch_name: &str) -> Option<String> {
+    let merge = config::get(&format!("branch.{}.merge", branch_name))?;
+    Some(merge.strip_prefix("refs/heads/").unwrap_or(&merge).to_string())
+}
+
+/// 设置（upstream为None时取消）分支的上游分支，branch_name为空时为当前分支
+fn set_upstream(branch_name: Option<String>, upstream: Option<String>) -> Result<(), BranchErr> {
+    let branch_name = match branch_name {
+        Some(name) => name,
+        None => match head::current_head() {
+            head::Head::Branch(name) => name,
+            head::Head::Detached(_) => {
+                println!("fatal: 当前不在任何分支上，无法设置上游分支");
+                return Err(BranchErr::HeadDetached);
+            }
+        },
+    };
+    if !head::list_local_branches().contains(&branch_name) {
+        println!("error: 分支 '{}' 不存在", branch_name);
+        return Err(BranchErr::BranchNoExist);
+    }
+    let mut config = Config::load();
+    match upstream {
+        Some(upstream) => {
+            let upstream = upstream.strip_prefix("refs/heads/").unwrap_or(&upstream).to_string();
+            if !head::list_local_branches().contains(&upstream) {
+                println!("error: 上游分支 '{}' 不存在", upstream);
+                return Err(BranchErr::BranchNoExist);
+            }
+            if upstream == branch_name {
+                println!("warning: 不能将分支 '{}' 设置为自己的上游分支", branch_name);
+                return Err(BranchErr::InvalidName);
             }
+            config.set(&format!("branch.{}.remote", branch_name), ".");
+            config.set(&format!("branch.{}.merge", branch_name), &format!("refs/heads/{}", upstream));
+            println!("branch '{}' set up to track '{}'.", branch_name, upstream);
         }
-        head::Head::Detached(commit_hash) => {
-            println!("* (HEAD detached at {}) {}", commit_hash.green(), commit_hash[0..7].green());
-            for branch in branches {
-                println!("  {}", branch);
+        None => {
+            let had_merge = config.unset(&format!("branch.{}.merge", branch_name));
+            config.unset(&format!("branch.{}.remote", branch_name));
+            if !had_merge {
+                println!("fatal: 分支 '{}' 没有上游分支", branch_name);
+                return Err(BranchErr::BranchNoExist);
             }
         }
     }
+    config.save();
+    Ok(())
 }
 
-pub fn branch(
-    new_branch: Option<String>,
-    commit_hash: Option<Hash>,
-    list: bool,
-    delete: Option<String>,
-    show_current: bool,
-) {
-    if new_branch.is_some() {
-        let basic_commit = commit_hash.unwrap_or_else(head::current_head_commit); // 默认使用当前commit
-        let _ = create_branch(new_branch.unwrap(), basic_commit);
-    } else if delete.is_some() {
-        let _ = delete_branch(delete.unwrap());
-    } else if show_current {
+/// 列出分支时每个分支的信息
+struct BranchInfo {
+    name: String,
+    commit: Commit,
+    is_head: bool,
+    upstream: Option<String>,
+    /// (ahead, behind)，上游分支不存在时为None
+    track: Option<(usize, usize)>,
+}
+
+impl BranchInfo {
+    fn new(name: String, is_head: bool) -> BranchInfo {
+        let commit = Commit::load(&head::get_branch_head(&name));
+        let upstream = upstream(&name);
+        let track = upstream.as_ref().and_then(|up| {
+            let up_commit = head::get_branch_head(up);
+            (!up_commit.is_empty()).then(|| rev_list::ahead_behind(&commit.get_hash(), &up_commit))
+        });
+        BranchInfo { name, commit, is_head, upstream, track }
+    }
+
+    fn subject(&self) -> String {
+        self.commit.get_message().lines().next().unwrap_or_default().to_string()
+    }
+
+    /// e.g. `ahead 1, behind 2`，与上游一致时为空，上游不存在时为 `gone`
+    fn track(&self) -> String {
+        match self.track {
+            None => "gone".to_string(),
+            Some((0, 0)) => "".to_string(),
+            Some((ahead, 0)) => format!("ahead {}", ahead),
+            Some((0, behind)) => format!("behind {}", behind),
+            Some((ahead, behind)) => format!("ahead {}, behind {}", ahead, behind),
+        }
+    }
+
+    /// `--format` 中的字段，e.g. `refname:short`
+    fn field(&self, name: &str) -> Option<String> {
+        let hash = self.commit.get_hash();
+        let upstream = self.upstream.clone().unwrap_or_default();
+        Some(match name {
+            "refname" => format!("refs/heads/{}", self.name),
+            "refname:short" => self.name.clone(),
+            "objectname" => hash,
+            "objectname:short" => hash[..7].to_string(),
+            "subject" => self.subject(),
+            "committerdate" => self.commit.get_date(),
+            "HEAD" => if self.is_head { "*" } else { " " }.to_string(),
+            "upstream" if !upstream.is_empty() => format!("refs/heads/{}", upstream),
+            "upstream:short" => upstream,
+            "upstream:track" if self.upstream.is_some() && !self.track().is_empty() => format!("[{}]", self.track()),
+            "upstream" | "upstream:track" => "".to_string(),
+            _ => return None,
+        })
+    }
+
+    fn format(&self, format: &str) -> Result<String, String> {
+        let mut result = String::new();
+        let mut rest = format;
+        while let Some(start) = rest.find("%(") {
+            result += &rest[..start];
+            let end = rest[start..]
+                .find(')')
+                .ok_or(format!("fatal: malformed format string {}", format))?;
+            let name = &rest[start + 2..start + end];
+            result += &self.field(name).ok_or(format!("fatal: unknown field name: {}", name))?;
+            rest = &rest[start + end + 1..];
+        }
+        Ok(result + rest)
+    }
+
+    /// `-v`：`<hash> [ahead 1] <subject>`，`-vv` 时同时显示上游分支名
+    fn verbose(&self, level: u8) -> String {
+        let track = match &self.upstream {
+            Some(up) if level > 1 && self.track().is_empty() => format!("[{}] ", up.blue()),
+            Some(up) if level > 1 => format!("[{}: {}] ", up.blue(), self.track()),
+            Some(_) if !self.track().is_empty() => format!("[{}] ", self.track()),
+            _ => "".to_string(),
+        };
+        format!("{} {}{}", &self.commit.get_hash()[..7], track, self.subject())
+    }
+}
+
+/// 按 `--sort` 的key排序：refname | committerdate | objectname，'-'开头时倒序
+fn sort_branches(branches: &mut [BranchInfo], key: &str) -> Result<(), String> {
+    let (reverse, field) = match key.strip_prefix('-') {
+        Some(field) => (true, field),
+        None => (false, key),
+    };
+    match field {
+        "refname" => branches.sort_by(|a, b| a.name.cmp(&b.name)),
+        "committerdate" => branches.sort_by_key(|b| b.commit.get_time()),
+        "objectname" => branches.sort_by_key(|b| b.commit.get_hash()),
+        _ => return Err(format!("fatal: unsupported sort key: {}", key)),
+    }
+    if reverse {
+        branches.reverse();
+    }
+    Ok(())
+}
+
+/** 列出分支，可以按是否合并到某个commit过滤
+<br>`-v` 显示每个分支的commit和相对上游分支的ahead/behind，`-vv` 同时显示上游分支名；`--format` 自定义每行的格式
+ */
+fn list_branches(options: &BranchOptions) {
+    let filter = match (&options.merged, &options.no_merged) {
+        (Some(rev), _) => Some((rev, true)),
+        (_, Some(rev)) => Some((rev, false)),
+        _ => None,
+    };
+    let filter = match filter {
+        Some((rev, merged)) => match revision::resolve_commit(rev) {
+            Ok(commit) => Some((commit, merged)),
+            Err(err) => return println!("{}", err),
+        },
+        None => None,
+    };
+    // 分支（或HEAD）指向的commit是否满足过滤条件
+    let keep = |commit: &Hash| match &filter {
+        Some((target, merged)) => !commit.is_empty() && is_ancestor(commit, target) == *merged,
+        None => true,
+    };
+    let current = match head::current_head() {
+        head::Head::Branch(branch_name) => Some(branch_name),
+        head::Head::Detached(_) => None,
+    };
+    let mut branches: Vec<BranchInfo> = head::list_local_branches()
+        .into_iter()
+        .filter(|b| keep(&head::get_branch_head(b)))
+        .map(|b| {
+            let is_head = current.as_ref() == Some(&b);
+            BranchInfo::new(b, is_head)
+        })
+        .collect();
+    if let Err(err) = sort_branches(&mut branches, options.sort.as_deref().unwrap_or("refname")) {
+        return println!("{}", err);
+    }
+
+    if let Some(format) = &options.format {
+        for branch in &branches {
+            match branch.format(format) {
+                Ok(line) => println!("{}", line),
+                Err(err) => return println!("{}", err),
+            }
+        }
+        return;
+    }
+    println!("list_branches");
+    let width = branches.iter().map(|b| b.name.len()).max().unwrap_or(0);
+    if let head::Head::Detached(commit_hash) = head::current_head() {
+        if keep(&commit_hash) {
+            println!("* (HEAD detached at {}) {}", commit_hash.green(), commit_hash[0..7].green());
+        }
+    }
+    for branch in &branches {
+        let name = if options.verbose > 0 {
+            format!("{:width$}", branch.name)
+        } else {
+            branch.name.clone()
+        };
+        let line = if options.verbose > 0 {
+            format!("{} {}", name, branch.verbose(options.verbose))
+        } else {
+            name
+        };
+        if branch.is_head {
+            println!("* {}", line.green());
+        } else {
+            println!("  {}", line);
+        }
+    }
+}
+
+/** 创建、删除、重命名、复制、列出分支
+<br>`-m`/`-c` 时，new_branch和commit_hash依次为原分支名和新分支名；只指定一个时为当前分支的新名称
+ */
+pub fn branch(new_branch: Option<String>, commit_hash: Option<Hash>, options: BranchOptions) {
+    if options.set_upstream.is_some() || options.unset_upstream {
+        let _ = set_upstream(new_branch, options.set_upstream);
+    } else if options.rename || options.copy {
+        let (old_name, new_name) = match commit_hash {
+            Some(new_name) => (new_branch, new_name),
+            None => (None, new_branch.expect("缺少新分支名")),
+        };
+        let _ = move_branch(old_name, new_name, options.force, options.copy);
+    } else if let Some(new_branch) = new_branch {
+        let basic_commit = commit_hash.unwrap_or_else(|| "HEAD".to_string()); // 默认使用当前commit
+        let _ = create_branch(new_branch, basic_commit);
+    } else if let Some(delete) = options.delete {
+        let _ = delete_branch(delete, options.force);
+    } else if options.show_current {
         show_current_branch();
-    } else if list {
+    } else if options.list || options.merged.is_some() || options.no_merged.is_some() {
         // 兜底list
-        list_branches();
+        list_branches(&options);
     } else {
         panic!("should not reach here")
     }
@@ -154,6 +470,20 @@ mod test {
         assert!(result.is_ok());
         assert!(head::list_local_branches().contains(&new_branch_two), "new branch not in list");
         assert!(head::get_branch_head(&new_branch_two) == commit_hash_one, "new branch head error");
+
+        // 分层分支名
+        let result = create_branch("feature/login".to_string(), commit_hash_two.clone());
+        assert!(result.is_ok());
+        assert!(head::list_local_branches().contains(&"feature/login".to_string()));
+        assert_eq!(revision::resolve_commit("feature/login").unwrap(), commit_hash_two);
+        let result = create_branch("feature".to_string(), commit_hash_two.clone());
+        assert!(matches!(result.unwrap_err(), BranchErr::BranchExist));
+        let result = create_branch("feature/login/v2".to_string(), commit_hash_two.clone());
+        assert!(matches!(result.unwrap_err(), BranchErr::BranchExist));
+        for name in ["bad name", "-x", "HEAD", "a..b", "x.lock", "x/"] {
+            let result = create_branch(name.to_string(), commit_hash_two.clone());
+            assert!(matches!(result.unwrap_err(), BranchErr::InvalidName), "{}", name);
+        }
     }
 
     #[test]
@@ -161,7 +491,7 @@ mod test {
         test::setup_with_clean_mit();
 
         // no commit: invalid object
-        let result = delete_branch("test_branch".to_string());
+        let result = delete_branch("test_branch".to_string(), false);
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), BranchErr::BranchNoExist));
         assert!(head::list_local_branches().is_empty());
@@ -177,8 +507,96 @@ mod test {
         assert!(head::get_branch_head(&new_branch) == commit_hash, "new branch head error");
 
         // branch exist
-        let result = delete_branch(new_branch.clone());
+        let result = delete_branch(new_branch.clone(), false);
         assert!(result.is_ok());
         assert!(!head::list_local_branches().contains(&new_branch), "new branch not in list");
+
+        // 未合并到HEAD的分支需要 -D
+        commands::switch(None, Some("unmerged".to_string()), false);
+        commands::commit::commit("unmerged commit".to_string(), true);
+        commands::switch(Some("master".to_string()), None, false);
+        let result = delete_branch("unmerged".to_string(), false);
+        assert!(matches!(result.unwrap_err(), BranchErr::NotMerged));
+        let result = delete_branch("unmerged".to_string(), true);
+        assert!(result.is_ok());
+        assert!(!head::list_local_branches().contains(&"unmerged".to_string()));
+    }
+
+    #[test]
+    fn test_move_branch() {
+        test::setup_with_clean_mit();
+        commands::commit::commit("test commit 1".to_string(), true);
+        let commit_hash = head::current_head_commit();
+        create_branch("dev".to_string(), commit_hash.clone()).ok();
+        let mut config = Config::load();
+        config.set("branch.dev.merge", "refs/heads/master");
+        config.save();
+
+        // 重命名其他分支，配置和reflog随之移动
+        assert!(move_branch(Some("dev".to_string()), "feature/dev".to_string(), false, false).is_ok());
+        assert_eq!(head::list_local_branches(), vec!["feature/dev", "master"]);
+        assert_eq!(Config::load().get("branch.feature/dev.merge"), Some("refs/heads/master".to_string()));
+        assert_eq!(Config::load().get("branch.dev.merge"), None);
+        assert_eq!(reflog::read("refs/heads/feature/dev").len(), 2);
+
+        // 重命名当前分支，HEAD随之移动
+        assert!(move_branch(None, "main".to_string(), false, false).is_ok());
+        assert!(matches!(head::current_head(), head::Head::Branch(name) if name == "main"));
+        assert_eq!(head::current_head_commit(), commit_hash);
+
+        // 已存在时需要 -M
+        let result = move_branch(Some("feature/dev".to_string()), "main".to_string(), false, false);
+        assert!(matches!(result.unwrap_err(), BranchErr::BranchExist));
+        assert!(move_branch(Some("main".to_string()), "feature".to_string(), false, false).is_err());
+
+        // 复制
+        assert!(move_branch(None, "copy".to_string(), false, true).is_ok());
+        assert_eq!(head::list_local_branches(), vec!["copy", "feature/dev", "main"]);
+        assert!(matches!(head::current_head(), head::Head::Branch(name) if name == "main"));
+        assert_eq!(Config::load().get("branch.copy.merge"), None);
+        assert!(move_branch(Some("feature/dev".to_string()), "copy".to_string(), true, false).is_ok());
+        assert_eq!(head::list_local_branches(), vec!["copy", "main"]);
+        assert_eq!(Config::load().get("branch.copy.merge"), Some("refs/heads/master".to_string()));
+    }
+
+    #[test]
+    fn test_upstream() {
+        test::setup_with_clean_mit();
+        commands::commit::commit("base".to_string(), true);
+        create_branch("dev".to_string(), "HEAD".to_string()).ok();
+        assert!(set_upstream(Some("dev".to_string()), Some("nothing".to_string())).is_err());
+        assert!(set_upstream(Some("dev".to_string()), Some("dev".to_string())).is_err());
+        assert!(set_upstream(Some("dev".to_string()), Some("master".to_string())).is_ok());
+        assert_eq!(upstream("dev"), Some("master".to_string()));
+
+        commands::switch(Some("dev".to_string()), None, false);
+        commands::commit::commit("dev 1".to_string(), true);
+        commands::commit::commit("dev 2".to_string(), true);
+        commands::switch(Some("master".to_string()), None, false);
+        commands::commit::commit("master 1".to_string(), true);
+
+        let dev = BranchInfo::new("dev".to_string(), false);
+        assert_eq!(dev.track, Some((2, 1)));
+        assert_eq!(
+            dev.format("%(HEAD)%(refname:short) %(upstream:short) %(upstream:track) %(subject)")
+                .unwrap(),
+            " dev master [ahead 2, behind 1] dev 2"
+        );
+        assert_eq!(dev.format("%(refname)").unwrap(), "refs/heads/dev");
+        assert!(dev.format("%(nothing)").is_err());
+        assert!(dev.verbose(1).ends_with("[ahead 2, behind 1] dev 2"));
+        let master = BranchInfo::new("master".to_string(), true);
+        assert_eq!(master.format("%(HEAD) %(upstream:track)|").unwrap(), "* |");
+
+        let mut branches = vec![dev, master];
+        sort_branches(&mut branches, "-committerdate").unwrap();
+        assert_eq!(branches[0].name, "master");
+        sort_branches(&mut branches, "refname").unwrap();
+        assert_eq!(branches[0].name, "dev");
+        assert!(sort_branches(&mut branches, "size").is_err());
+
+        assert!(set_upstream(Some("dev".to_string()), None).is_ok());
+        assert_eq!(upstream("dev"), None);
+        assert!(set_upstream(Some("dev".to_string()), None).is_err());
     }
 }
@@ -0,0 +1,102 @@
+use crate::{
+    commands::sequencer::{self, Action, SequenceOptions},
+    models::Hash,
+    utils::util,
+};
+
+/// 正在cherry-pick的commit，CHERRY_PICK_HEAD不存在时为None
+pub fn cherry_pick_head() -> Option<Hash> {
+    sequencer::current(Action::Pick)
+}
+
+/** 将已有commit的更改应用到HEAD上并提交，保留原作者和提交信息
+<br>-n 只应用到工作区和暂存区；-x 在提交信息中记录来源
+<br>冲突时停止，解决后 `--continue`，或 `--skip` 跳过当前commit，`--abort` 恢复到开始前的状态
+ */
+pub fn cherry_pick(
+    commits: Vec<String>,
+    no_commit: bool,
+    record_origin: bool,
+    continue_pick: bool,
+    skip: bool,
+    abort: bool,
+) {
+    util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
+    if continue_pick {
+        return sequencer::resume(Action::Pick);
+    }
+    if skip {
+        return sequencer::skip(Action::Pick);
+    }
+    if abort {
+        return sequencer::abort(Action::Pick);
+    }
+    let options = SequenceOptions { no_commit, record_origin, mainline: None };
+    sequencer::start(Action::Pick, commits, options);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::{self as cmd, restore::reset_to_commit, status::changes_to_be_committed},
+        models::{head, Commit},
+        utils::test,
+    };
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    #[test]
+    fn test_cherry_pick() {
+        test::setup_with_empty_workdir();
+        test::write_and_commit("a.txt", "1\n2\n3\n", "base");
+        cmd::switch(None, Some("feature".to_string()), false);
+        let f1 = test::write_and_commit("a.txt", "one\n2\n3\n", "f1");
+        let f2 = test::write_and_commit("b.txt", "b\n", "f2");
+        let f3 = test::write_and_commit("a.txt", "one\n2\nthree\n", "f3");
+        cmd::switch(Some("master".to_string()), None, false);
+        let master = test::write_and_commit("a.txt", "1\n2\nTHREE\n", "master");
+
+        // -x：记录来源
+        cherry_pick(vec![f1.clone()], false, true, false, false, false);
+        let picked = Commit::load(&head::current_head_commit());
+        assert_eq!(picked.get_parent_hash(), vec![master]);
+        assert_eq!(picked.get_message(), format!("f1\n\n(cherry picked from commit {})", f1));
+        assert_eq!(picked.get_author(), Commit::load(&f1).get_author());
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "one\n2\nTHREE\n");
+
+        // -n：只暂存
+        let head = head::current_head_commit();
+        cherry_pick(vec![f2.clone()], true, false, false, false, false);
+        assert_eq!(head::current_head_commit(), head);
+        assert_eq!(changes_to_be_committed().new, vec![PathBuf::from("b.txt")]);
+        reset_to_commit(&head);
+        assert!(!Path::new("b.txt").exists());
+
+        // 冲突：--abort恢复
+        cherry_pick(vec![f3.clone()], false, false, false, false, false);
+        assert_eq!(cherry_pick_head(), Some(f3.clone()));
+        assert!(fs::read_to_string("a.txt").unwrap().contains("<<<<<<< HEAD"));
+        cherry_pick(vec![], false, false, false, false, true);
+        assert!(cherry_pick_head().is_none());
+        assert_eq!(head::current_head_commit(), head);
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "one\n2\nTHREE\n");
+
+        // 范围 + 冲突后--continue
+        cherry_pick(vec!["master..feature".to_string()], false, false, false, false, false);
+        assert_eq!(cherry_pick_head(), Some(f3.clone()));
+        test::ensure_file(Path::new("a.txt"), Some("one\n2\nThree\n"));
+        cmd::add(vec![], true, false);
+        cherry_pick(vec![], false, false, true, false, false);
+        assert!(cherry_pick_head().is_none());
+        let picked = Commit::load(&head::current_head_commit());
+        assert_eq!(picked.get_message(), "f3");
+        assert_eq!(Commit::load(&picked.get_parent_hash()[0]).get_message(), "f2");
+        assert!(Path::new("b.txt").exists());
+    }
+}
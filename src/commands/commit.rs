@@ -1,25 +1,47 @@
-use crate::models::*;
+use crate::{models::*, utils::util};
 
-use super::status;
+use super::{diff::display_path, merge, status};
 
+/// 提交暂存区；存在MERGE_HEAD时生成merge commit，有未解决的冲突时拒绝提交
 pub fn commit(message: String, allow_empty: bool) {
+    if !util::check_worktree_exist() {
+        return;
+    }
     let index = Index::get_instance();
-    if !allow_empty && status::changes_to_be_committed().is_empty() {
+    let conflicts = index.get_conflicts();
+    if !conflicts.is_empty() {
+        println!("error: Committing is not possible because you have unmerged files.");
+        for (path, _) in conflicts {
+            println!("U\t{}", display_path(&util::to_workdir_relative_path(&path)));
+        }
+        println!("hint: Fix them up in the work tree, and then use 'mit add <file>' as appropriate to mark resolution");
+        return;
+    }
+    let merge_head = merge::merge_head();
+    if !allow_empty && merge_head.is_none() && status::changes_to_be_committed().is_empty() {
         panic!("工作区没有任何改动，不需要提交");
     }
 
     let current_head = head::current_head();
     let current_commit_hash = head::current_head_commit();
 
-    let mut commit = {
-        if current_commit_hash.is_empty() {
-            Commit::new(index, vec![], message.clone())
-        } else {
-            Commit::new(index, vec![current_commit_hash.clone()], message.clone())
-        }
+    let mut parents = Vec::new();
+    if !current_commit_hash.is_empty() {
+        parents.push(current_commit_hash.clone());
+    }
+    parents.extend(merge_head.clone());
+    let kind = match parents.len() {
+        0 => "commit (initial)",
+        1 => "commit",
+        _ => "commit (merge)",
     };
+    let reason = format!("{}: {}", kind, message.lines().next().unwrap_or_default());
+    let mut commit = Commit::new(index, parents, message.clone());
     let commit_hash = commit.save();
-    head::update_head_commit(&commit_hash);
+    head::update_head_commit(&commit_hash, &reason);
+    if merge_head.is_some() {
+        merge::clear_merge_state();
+    }
 
     match current_head {
         head::Head::Branch(branch_name) => {
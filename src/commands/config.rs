@@ -0,0 +1,34 @@
+use crate::utils::{config::Config, util};
+
+/** 读写仓库配置 .mit/config
+<br>只有key时打印值，有value时写入 */
+pub fn config(key: Option<String>, value: Option<String>, unset: bool, list: bool) {
+    util::check_repo_exist();
+    let mut config = Config::load();
+    if list {
+        for (k, v) in config.list() {
+            println!("{}={}", k, v);
+        }
+        return;
+    }
+    let key = match key {
+        Some(key) => key,
+        None => {
+            println!("error: key is required");
+            return;
+        }
+    };
+    if unset {
+        if !config.unset(&key) {
+            println!("error: key '{}' not found", key);
+        }
+        config.save();
+    } else if let Some(value) = value {
+        if !config.set(&key, &value) {
+            return println!("error: key does not contain a section: {}", key);
+        }
+        config.save();
+    } else if let Some(value) = config.get(&key) {
+        println!("{}", value);
+    }
+}
@@ -0,0 +1,266 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use colored::Colorize;
+
+use crate::{
+    models::{head, Blob, Commit, Hash, Index},
+    utils::{
+        diff::{self, DiffLine, DiffOptions},
+        util, PathExt,
+    },
+};
+
+use super::status;
+
+const DEFAULT_CONTEXT: usize = 3;
+
+/// 读取blob内容，空hash视为空文件
+fn blob_content(hash: &Hash) -> String {
+    if hash.is_empty() {
+        String::new()
+    } else {
+        Blob::load(hash).get_content()
+    }
+}
+
+/** 一个文件的统一diff（已着色），old/new为None表示文件不存在(新增/删除)
+<br>新旧路径可以不同（用于重命名），headers为额外的头部信息；路径为相对workdir的路径
+ */
+pub fn file_diff_lines(
+    old_path: &str,
+    new_path: &str,
+    old: Option<&str>,
+    new: Option<&str>,
+    options: &DiffOptions,
+    headers: &[String],
+) -> Vec<String> {
+    colorize_diff_lines(&format_file_diff(old_path, new_path, old, new, options, headers))
+}
+
+/// 一个文件的统一diff（未着色），没有差异时为空
+fn format_file_diff(
+    old_path: &str,
+    new_path: &str,
+    old: Option<&str>,
+    new: Option<&str>,
+    options: &DiffOptions,
+    headers: &[String],
+) -> Vec<String> {
+    let hunks = diff::diff_hunks(old.unwrap_or_default(), new.unwrap_or_default(), options, DEFAULT_CONTEXT);
+    if hunks.is_empty() && old.is_some() && new.is_some() && old_path == new_path {
+        return Vec::new(); // 忽略空白后没有差异
+    }
+    let mut lines = vec![format!("diff --mit a/{} b/{}", old_path, new_path)];
+    lines.extend(headers.iter().cloned());
+    if old.is_none() {
+        lines.push("new file".to_string());
+    } else if new.is_none() {
+        lines.push("deleted file".to_string());
+    }
+    let old_label = if old.is_some() {
+        format!("a/{}", old_path)
+    } else {
+        "/dev/null".to_string()
+    };
+    let new_label = if new.is_some() {
+        format!("b/{}", new_path)
+    } else {
+        "/dev/null".to_string()
+    };
+    lines.push(format!("--- {}", old_label));
+    lines.push(format!("+++ {}", new_label));
+    for hunk in hunks {
+        lines.push(hunk.header());
+        for line in hunk.lines {
+            lines.push(match line {
+                DiffLine::Context(l) => format!(" {}", l),
+                DiffLine::Delete(l) => format!("-{}", l),
+                DiffLine::Insert(l) => format!("+{}", l),
+            });
+        }
+    }
+    lines
+}
+
+/// 着色：文件头加粗，hunk头青色，删除红色，新增绿色
+fn colorize_diff_lines(lines: &[String]) -> Vec<String> {
+    let mut in_header = true;
+    let mut result = Vec::new();
+    for line in lines {
+        if line.starts_with("diff --mit ") {
+            in_header = true;
+        }
+        result.push(if in_header {
+            in_header = !line.starts_with("+++ ");
+            line.bold().to_string()
+        } else if line.starts_with("@@") {
+            line.cyan().to_string()
+        } else if line.starts_with('-') {
+            line.red().to_string()
+        } else if line.starts_with('+') {
+            line.green().to_string()
+        } else {
+            line.clone()
+        });
+    }
+    result
+}
+
+/// 统一路径分隔符为'/'
+pub fn display_path(path: &std::path::Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// 工作区 vs 暂存区
+fn diff_worktree(paths: &Vec<PathBuf>, options: &DiffOptions) -> Vec<String> {
+    let index = Index::get_instance();
+    let mut lines = Vec::new();
+    let changes = status::changes_to_be_staged().filter_abs(paths);
+    let mut files: Vec<PathBuf> = changes.modified.into_iter().chain(changes.deleted).collect();
+    files.sort();
+    for file in files {
+        let old = blob_content(&index.get_hash(&file).unwrap_or_default());
+        let new = if file.exists() {
+            Some(util::read_workfile(&file))
+        } else {
+            None
+        };
+        let path = display_path(&file.to_relative_workdir());
+        lines.extend(format_file_diff(&path, &path, Some(&old), new.as_deref(), options, &[]));
+    }
+    lines
+}
+
+/// 暂存区 vs HEAD
+fn diff_cached(paths: &Vec<PathBuf>, options: &DiffOptions) -> Vec<String> {
+    let index = Index::get_instance();
+    let mut lines = Vec::new();
+    let head_commit = head::current_head_commit();
+    let head_blobs: HashMap<PathBuf, Hash> = if head_commit.is_empty() {
+        HashMap::new()
+    } else {
+        Commit::load(&head_commit)
+            .get_tree()
+            .get_recursive_blobs()
+            .into_iter()
+            .collect()
+    };
+    let changes = status::changes_to_be_committed();
+    let index_hash = |file: &PathBuf| index.get_hash(&file.to_absolute_workdir()).unwrap_or_default();
+    for (rename, kind) in changes
+        .renamed
+        .iter()
+        .map(|r| (r, "rename"))
+        .chain(changes.copied.iter().map(|r| (r, "copy")))
+    {
+        if !rename.from.to_absolute_workdir().include_in(paths) && !rename.to.to_absolute_workdir().include_in(paths) {
+            continue;
+        }
+        let (from, to) = (display_path(&rename.from), display_path(&rename.to));
+        let headers = [
+            format!("similarity index {}%", rename.score),
+            format!("{} from {}", kind, from),
+            format!("{} to {}", kind, to),
+        ];
+        let old = blob_content(&head_blobs[&rename.from]);
+        let new = blob_content(&index_hash(&rename.to));
+        lines.extend(format_file_diff(&from, &to, Some(&old), Some(&new), options, &headers));
+    }
+    let mut files: Vec<(PathBuf, bool, bool)> = Vec::new(); // (path, in_head, in_index)
+    files.extend(changes.new.iter().map(|p| (p.clone(), false, true)));
+    files.extend(changes.modified.iter().map(|p| (p.clone(), true, true)));
+    files.extend(changes.deleted.iter().map(|p| (p.clone(), true, false)));
+    files.sort();
+    for (file, in_head, in_index) in files {
+        if !file.to_absolute_workdir().include_in(paths) {
+            continue;
+        }
+        let old = in_head.then(|| blob_content(&head_blobs[&file]));
+        let new = in_index.then(|| blob_content(&index_hash(&file)));
+        let path = display_path(&file);
+        lines.extend(format_file_diff(&path, &path, old.as_deref(), new.as_deref(), options, &[]));
+    }
+    lines
+}
+
+/** 显示更改
+<br>默认比较工作区与暂存区，--cached比较暂存区与HEAD */
+pub fn diff(paths: Vec<String>, cached: bool, options: DiffOptions) {
+    util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
+    let mut paths: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+    if paths.is_empty() {
+        paths.push(util::get_working_dir().unwrap());
+    }
+    let paths = paths.iter().map(|p| p.to_absolute()).collect();
+    let lines = if cached {
+        diff_cached(&paths, &options)
+    } else {
+        diff_worktree(&paths, &options)
+    };
+    colorize_diff_lines(&lines).iter().for_each(|line| println!("{}", line));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{commands as cmd, utils::test};
+    use std::path::Path;
+
+    fn diff_all(cached: bool, options: DiffOptions) -> Vec<String> {
+        let paths = vec![util::get_working_dir().unwrap()];
+        if cached {
+            diff_cached(&paths, &options)
+        } else {
+            diff_worktree(&paths, &options)
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        test::setup_with_empty_workdir();
+        test::ensure_file(Path::new("a.txt"), Some("one\ntwo\nthree\n"));
+        cmd::add(vec![], true, false);
+        assert_eq!(
+            diff_all(true, DiffOptions::default()),
+            [
+                "diff --mit a/a.txt b/a.txt",
+                "new file",
+                "--- /dev/null",
+                "+++ b/a.txt",
+                "@@ -0,0 +1,3 @@",
+                "+one",
+                "+two",
+                "+three"
+            ]
+        );
+        assert!(diff_all(false, DiffOptions::default()).is_empty());
+        cmd::commit("init".to_string(), false);
+
+        test::ensure_file(Path::new("a.txt"), Some("one\n2\nthree\n"));
+        let expected = [
+            "diff --mit a/a.txt b/a.txt",
+            "--- a/a.txt",
+            "+++ b/a.txt",
+            "@@ -1,3 +1,3 @@",
+            " one",
+            "-two",
+            "+2",
+            " three",
+        ];
+        assert_eq!(diff_all(false, DiffOptions::default()), expected);
+        assert!(diff_all(true, DiffOptions::default()).is_empty());
+
+        // 只有空白变化时忽略
+        test::ensure_file(Path::new("a.txt"), Some("one\ntwo \nthree\n"));
+        assert_eq!(diff_all(false, DiffOptions::default()).len(), 8);
+        assert!(diff_all(false, DiffOptions { ignore_all_space: true, ..Default::default() }).is_empty());
+
+        test::ensure_file(Path::new("a.txt"), Some("one\n2\nthree\n"));
+        cmd::add(vec![], true, false);
+        assert_eq!(diff_all(true, DiffOptions::default()), expected);
+        assert!(diff_all(false, DiffOptions::default()).is_empty());
+    }
+}
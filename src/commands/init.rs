@@ -1,15 +1,76 @@
-use crate::utils::util::ROOT_DIR;
-use std::{env, fs, io};
+use crate::{
+    models::head,
+    utils::{
+        config::Config,
+        util::{self, MIT_DIR, ROOT_DIR},
+    },
+};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// init的可选参数
+#[derive(Debug, Clone, Default)]
+pub struct InitOptions {
+    /// 仓库目录，默认为当前目录，不存在时创建
+    pub directory: Option<String>,
+    /// 初始分支名，默认为配置 `init.defaultBranch`，否则为master
+    pub initial_branch: Option<String>,
+    /// 模板目录，其中的文件（hooks、ignore文件、config等）会被复制到 .mit 中
+    pub template: Option<String>,
+    /// 创建裸仓库：没有工作区，仓库目录本身即为 .mit
+    pub bare: bool,
+}
 
 /**
 初始化mit仓库 创建.mit/objects .mit/refs/heads .mit/HEAD
-<br>并设置 .mit 为隐藏文件夹
+<br>并设置 .mit 为隐藏文件夹；`--bare` 时直接在仓库目录下创建，不设置隐藏
+<br>设置了 MIT_DIR（`--mit-dir`）时在该目录创建仓库，工作区为当前目录
 <br>无法重复初始化
 */
-pub fn init() -> io::Result<()> {
-    let dir = env::current_dir()?;
-    let mit_dir = dir.join(ROOT_DIR);
-    if mit_dir.exists() {
+pub fn init(options: InitOptions) -> io::Result<()> {
+    // 先检查参数，出错时不创建任何目录
+    let branch = options
+        .initial_branch
+        .or_else(|| Config::load_global().get("init.defaultBranch"))
+        .unwrap_or("master".to_string());
+    if branch == "HEAD" || branch.starts_with('-') || !head::check_ref_name(&branch) {
+        println!("fatal: invalid initial branch name: '{}'", branch);
+        return Ok(());
+    }
+    let template = options.template.map(PathBuf::from).filter(|template| {
+        if !template.is_dir() {
+            println!("warning: templates not found in {}", template.display());
+        }
+        template.is_dir()
+    });
+
+    let mit_dir_env = util::env_path(MIT_DIR);
+    let in_mit_dir = mit_dir_env.is_some();
+    if in_mit_dir && options.directory.is_some() {
+        println!("fatal: cannot specify a directory together with --mit-dir (MIT_DIR)");
+        return Ok(());
+    }
+
+    let dir = match (&options.directory, mit_dir_env) {
+        (Some(directory), _) => {
+            fs::create_dir_all(directory)?;
+            fs::canonicalize(directory)?
+        }
+        (None, Some(mit_dir)) => {
+            fs::create_dir_all(&mit_dir)?;
+            mit_dir
+        }
+        (None, None) => env::current_dir()?,
+    };
+    // 指定了MIT_DIR时仓库就是该目录，与裸仓库相同，不再创建 .mit 子目录
+    let mit_dir = if options.bare || in_mit_dir {
+        dir.clone()
+    } else {
+        dir.join(ROOT_DIR)
+    };
+    if dir.join(ROOT_DIR).exists() || mit_dir.join("HEAD").exists() {
         println!("!Already a mit repo - [{}]", dir.display());
         return Ok(());
     }
@@ -19,13 +80,37 @@ pub fn init() -> io::Result<()> {
     for dir in &dirs {
         fs::create_dir_all(dir)?;
     }
-    fs::write(mit_dir.join("HEAD"), "ref: refs/heads/master\n")?;
+    fs::write(mit_dir.join("HEAD"), format!("ref: refs/heads/{}\n", branch))?;
+    if let Some(template) = template {
+        copy_template(&template, &mit_dir)?;
+    }
+
+    let mut config = Config::load_from(mit_dir.join("config"));
+    config.set("core.bare", &options.bare.to_string());
+    config.save();
 
-    set_dir_hidden(mit_dir.to_str().unwrap())?; // 设置目录隐藏 (跨平台)
+    if mit_dir != dir {
+        set_dir_hidden(mit_dir.to_str().unwrap())?; // 设置目录隐藏 (跨平台)
+    }
     println!("Initialized empty mit repository in {}", dir.display());
     Ok(())
 }
 
+/// 递归复制模板目录，不覆盖已存在的文件
+fn copy_template(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_template(&entry.path(), &target)?;
+        } else if !target.exists() {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(target_os = "windows")]
 fn set_dir_hidden(dir: &str) -> io::Result<()> {
     use std::process::Command;
@@ -39,3 +124,129 @@ fn set_dir_hidden(dir: &str) -> io::Result<()> {
     let _ = dir;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands as cmd,
+        utils::{test, util},
+    };
+
+    #[test]
+    fn test_init() {
+        test::setup_with_clean_mit();
+        let repo = PathBuf::from("init_test_repo");
+        let _ = fs::remove_dir_all(&repo);
+        let template = repo.join("template");
+        test::ensure_file(&template.join("hooks/pre-commit"), Some("#!/bin/sh\n"));
+        test::ensure_file(&template.join("info/exclude"), Some("*.log\n"));
+        test::ensure_file(&template.join("config"), Some("[user]\n\tname = tester\n"));
+        test::ensure_file(&template.join("HEAD"), Some("ref: refs/heads/template\n"));
+
+        let options = InitOptions {
+            directory: Some(repo.join("new").to_str().unwrap().to_string()),
+            initial_branch: Some("main".to_string()),
+            template: Some(template.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+        init(options).unwrap();
+        let mit_dir = repo.join("new").join(ROOT_DIR);
+        assert_eq!(fs::read_to_string(mit_dir.join("HEAD")).unwrap(), "ref: refs/heads/main\n");
+        assert!(mit_dir.join("refs/heads").is_dir());
+        assert_eq!(fs::read_to_string(mit_dir.join("hooks/pre-commit")).unwrap(), "#!/bin/sh\n");
+        assert_eq!(fs::read_to_string(mit_dir.join("info/exclude")).unwrap(), "*.log\n");
+        assert_eq!(Config::load_from(mit_dir.join("config")).get("user.name"), Some("tester".to_string()));
+
+        // 全局配置 init.defaultBranch
+        let home = env::var_os("HOME");
+        test::ensure_file(&repo.join("home/.mitconfig"), Some("[init]\n\tdefaultBranch = trunk\n"));
+        env::set_var("HOME", fs::canonicalize(repo.join("home")).unwrap());
+        let options = InitOptions {
+            directory: Some(repo.join("default").to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+        init(options).unwrap();
+        match home {
+            Some(home) => env::set_var("HOME", home),
+            None => env::remove_var("HOME"),
+        }
+        let head = fs::read_to_string(repo.join("default").join(ROOT_DIR).join("HEAD")).unwrap();
+        assert_eq!(head, "ref: refs/heads/trunk\n");
+
+        // 非法的分支名
+        let options = InitOptions {
+            directory: Some(repo.join("bad").to_str().unwrap().to_string()),
+            initial_branch: Some("bad..name".to_string()),
+            ..Default::default()
+        };
+        init(options).unwrap();
+        assert!(!repo.join("bad").exists()); // 不会留下空目录
+
+        // 裸仓库
+        let options = InitOptions {
+            directory: Some(repo.join("central.mit").to_str().unwrap().to_string()),
+            bare: true,
+            ..Default::default()
+        };
+        init(options).unwrap();
+        let bare = repo.join("central.mit");
+        assert!(bare.join("HEAD").is_file() && bare.join("objects").is_dir() && bare.join("refs/heads").is_dir());
+        assert!(!bare.join(ROOT_DIR).exists());
+        assert_eq!(Config::load_from(bare.join("config")).get("core.bare"), Some("true".to_string()));
+
+        // 需要工作区的命令直接返回，不修改仓库
+        let bare = fs::canonicalize(bare).unwrap();
+        test::ensure_file(&bare.join("a.txt"), Some("a\n"));
+        let snapshot = || {
+            let mut files = util::list_files(&bare).unwrap();
+            files.sort();
+            files
+                .into_iter()
+                .map(|file| (fs::read(&file).unwrap(), file))
+                .collect::<Vec<_>>()
+        };
+        let before = snapshot();
+        let cwd = env::current_dir().unwrap();
+        env::set_current_dir(&bare).unwrap();
+        assert!(util::is_bare());
+        assert!(util::get_working_dir().is_none());
+        assert!(!util::check_worktree_exist());
+        cmd::status();
+        cmd::add(vec!["a.txt".to_string()], false, false);
+        cmd::rm(vec!["a.txt".to_string()], true, false).unwrap();
+        cmd::commit("bare".to_string(), true);
+        cmd::switch(None, Some("topic".to_string()), false);
+        cmd::restore(vec!["a.txt".to_string()], None, true, true);
+        cmd::reset(None, cmd::reset::ResetMode::Hard, vec![]);
+        cmd::stash::push(None, true);
+        assert_eq!(snapshot(), before);
+        assert!(!bare.join("index").exists());
+        assert!(head::current_head_commit().is_empty());
+        assert!(head::list_local_branches().is_empty());
+        // 修改core.bare后重新判断，而不是沿用缓存
+        let mut config = Config::load();
+        config.set("core.bare", "false");
+        config.save();
+        assert!(!util::is_bare());
+        env::set_current_dir(cwd).unwrap();
+
+        // --mit-dir：在指定目录创建仓库，当前目录为工作区
+        let store = fs::canonicalize(&repo).unwrap().join("store");
+        env::set_var(MIT_DIR, &store);
+        init(InitOptions {
+            directory: Some(repo.join("x").to_str().unwrap().to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!repo.join("x").exists() && !store.exists());
+        init(InitOptions::default()).unwrap();
+        assert!(store.join("HEAD").is_file() && store.join("objects").is_dir());
+        assert!(!store.join(ROOT_DIR).exists());
+        assert_eq!(Config::load_from(store.join("config")).get("core.bare"), Some("false".to_string()));
+        assert_eq!(util::get_storage_path().unwrap(), store);
+        assert_eq!(util::get_working_dir().unwrap(), util::cur_dir());
+        env::remove_var(MIT_DIR);
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
@@ -1,70 +1,118 @@
-use crate::models::{head, Commit};
+use std::collections::HashMap;
+
+use crate::{
+    commands::{diff::display_path, rev_list::RevQuery, status, tag},
+    models::{head, Commit, Hash},
+};
 use colored::Colorize;
 
 const DEFAULT_LOG_NUMBER: usize = 10;
 
-pub fn log(all: bool, number: Option<usize>) {
-    println!("log all: {:?}, number: {:?}", all, number);
-    let _ = __log(all, number);
+/// 显示提交历史，revs为空时从HEAD开始；支持 `A..B`、`A...B`、`^A`
+pub fn log(revs: Vec<String>, all: bool, number: Option<usize>, name_status: bool) {
+    let _ = __log(revs, all, number, name_status);
 }
 
-fn __log(all: bool, number: Option<usize>) -> usize {
-    let mut log_count = 0usize;
+/// 打印commit相对于第一个parent的文件变更（A/M/D/R/C）
+pub fn print_name_status(commit: &Commit) {
+    let blobs = commit.get_tree().get_recursive_blobs();
+    let parent_blobs = match commit.get_parent_hash().first() {
+        Some(parent) => Commit::load(parent).get_tree().get_recursive_blobs(),
+        None => Vec::new(),
+    };
+    let changes = status::compare_blobs(&parent_blobs, &blobs, &status::RenameOptions::from_config());
+    let mut lines: Vec<(String, String)> = Vec::new(); // (排序用路径, 输出)
+    for path in &changes.new {
+        lines.push((display_path(path), format!("A\t{}", display_path(path))));
+    }
+    for path in &changes.modified {
+        lines.push((display_path(path), format!("M\t{}", display_path(path))));
+    }
+    for path in &changes.deleted {
+        lines.push((display_path(path), format!("D\t{}", display_path(path))));
+    }
+    for (kind, renames) in [("R", &changes.renamed), ("C", &changes.copied)] {
+        for r in renames {
+            let (from, to) = (display_path(&r.from), display_path(&r.to));
+            lines.push((to.clone(), format!("{}{:03}\t{}\t{}", kind, r.score, from, to)));
+        }
+    }
+    lines.sort();
+    for (_, line) in lines {
+        println!("{}", line);
+    }
+    println!();
+}
+
+/// commit到指向它的引用名，e.g. `HEAD -> master, dev`；每次log只构建一次
+fn decorations() -> HashMap<Hash, Vec<String>> {
+    let mut refs: HashMap<Hash, Vec<String>> = HashMap::new();
+    let current = match head::current_head() {
+        head::Head::Branch(branch) => Some(branch),
+        head::Head::Detached(_) => None,
+    };
+    let head_commit = head::current_head_commit();
+    if !head_commit.is_empty() {
+        let name = match &current {
+            Some(branch) => format!("HEAD -> {}", branch),
+            None => "HEAD".to_string(),
+        };
+        refs.entry(head_commit).or_default().push(name);
+    }
+    for branch in head::list_local_branches() {
+        if Some(&branch) != current.as_ref() {
+            refs.entry(head::get_branch_head(&branch)).or_default().push(branch);
+        }
+    }
+    for tag in head::list_tags() {
+        if let Some(commit) = tag::tag_commit(&tag) {
+            refs.entry(commit).or_default().push(format!("tag: {}", tag));
+        }
+    }
+    refs
+}
 
-    let head = head::current_head();
-    let mut branch_name: Option<String> = None;
-    let mut head_commit = match head {
-        head::Head::Branch(_branch_name) => {
-            let commit = head::get_branch_head(&_branch_name);
-            branch_name = Some(_branch_name.clone());
-            if commit.is_empty() {
-                println!("当前分支{:?}没有任何提交", _branch_name);
+fn __log(revs: Vec<String>, all: bool, number: Option<usize>, name_status: bool) -> usize {
+    if revs.is_empty() {
+        if let head::Head::Branch(branch_name) = head::current_head() {
+            if head::get_branch_head(&branch_name).is_empty() {
+                println!("当前分支{:?}没有任何提交", branch_name);
                 return 0;
             }
-            commit
         }
-        head::Head::Detached(commit_hash) => commit_hash,
-    };
-
-    let mut number = match number {
-        Some(number) => number,
-        None => DEFAULT_LOG_NUMBER,
+    }
+    let mut commits = match RevQuery::parse(&revs) {
+        Ok(query) => query.run(),
+        Err(err) => {
+            println!("{}", err);
+            return 0;
+        }
     };
+    if !all {
+        commits.truncate(number.unwrap_or(DEFAULT_LOG_NUMBER));
+    }
 
-    let mut first = true;
-    loop {
-        log_count += 1;
-        let commit = Commit::load(&head_commit);
-        if first {
-            // TODO: (HEAD -> ttt, ad2)
-            first = false;
-            print!("{}{}{}{}", "commit ".yellow(), commit.get_hash().yellow(), "(".yellow(), "HEAD".blue());
-            if let Some(ref branch_name) = branch_name {
-                print!("{}", format!(" -> {}", branch_name).blue());
-            }
-            println!("{}", ")".yellow());
+    let decorations = decorations();
+    for commit in &commits {
+        let refs = decorations
+            .get(&commit.get_hash())
+            .map(|refs| refs.join(", "))
+            .unwrap_or_default();
+        if refs.is_empty() {
+            println!("{}", format!("commit {}", commit.get_hash()).yellow());
         } else {
-            println!("{}{}{}{}{}", "commit ".yellow(), head_commit.yellow(), "(".yellow(), "HEAD".blue(), ")".yellow());
+            println!("{}{}{}", format!("commit {} (", commit.get_hash()).yellow(), refs.blue(), ")".yellow());
         }
         println!("Author: {}", commit.get_author());
         println!("Date:   {}", commit.get_date());
         println!();
         println!("    {}", commit.get_message());
         println!();
-
-        if all == false {
-            if number > 1 {
-                number -= 1;
-            } else {
-                break;
-            }
-        }
-        if commit.get_parent_hash().is_empty() {
-            break;
+        if name_status {
+            print_name_status(commit);
         }
-        head_commit = commit.get_parent_hash().first().unwrap().clone();
     }
-    log_count
+    commits.len()
 }
 
 #[cfg(test)]
@@ -74,10 +122,11 @@ mod test {
     #[test]
     fn test_log() {
         test::setup_with_clean_mit();
-        assert_eq!(super::__log(false, None), 0);
+        assert_eq!(super::__log(vec![], false, None, false), 0);
         commands::commit::commit("test commit 2".into(), true);
-        assert_eq!(super::__log(false, Some(1)), 1);
+        assert_eq!(super::__log(vec![], false, Some(1), false), 1);
         commands::commit::commit("test commit 3".into(), true);
-        assert_eq!(super::__log(false, None), 2);
+        assert_eq!(super::__log(vec![], false, None, true), 2);
+        assert_eq!(super::__log(vec!["HEAD~1..".into()], false, None, false), 1);
     }
 }
@@ -1,31 +1,46 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
 use crate::{
-    commands::{self, status::*},
-    models::{head, Commit, Hash},
-    utils::{store, util},
+    commands::{
+        self,
+        diff::display_path,
+        merge_base,
+        restore::{reset_to_commit, restore_index, restore_worktree},
+        status::*,
+    },
+    models::{head, Blob, Commit, ConflictStages, Hash, Index},
+    utils::{
+        attributes::Attributes,
+        config,
+        diff::{self, DiffOptions, MergeChunk},
+        path_ext::PathExt,
+        revision, util,
+    },
 };
 
 enum MergeErr {
     NoFastForward,
     NoClean,
+    /// 三路合并存在冲突的文件
+    Conflict(Vec<PathBuf>),
 }
 
+/// current是否是target的祖先（可以fast forward），未提交过的分支总是可以
 fn check_ff(current: &Hash, target: Hash) -> Result<bool, MergeErr> {
-    let target_commit = Commit::load(&target);
-    // 检查current是否是target的祖先
-    if *current == target_commit.get_hash() {
-        return Ok(true);
-    }
-    for parent in target_commit.get_parent_hash() {
-        let result = check_ff(current, parent);
-        if result.is_ok() {
-            return result;
-        }
+    if current.is_empty() || merge_base::is_ancestor(current, &target) {
+        Ok(true)
+    } else {
+        Err(MergeErr::NoFastForward)
     }
-    Err(MergeErr::NoFastForward)
 }
 
 /** commit 以fast forward到形式合并到当前分支 */
-fn merge_ff(commit_hash: String) -> Result<(), MergeErr> {
+fn merge_ff(commit_hash: String, name: &str) -> Result<(), MergeErr> {
     // 检查更改
     if !changes_to_be_staged().is_empty() {
         println!("fatal: 你有未暂存的更改，切换分支会导致更改丢失");
@@ -44,8 +59,10 @@ fn merge_ff(commit_hash: String) -> Result<(), MergeErr> {
     let head = head::current_head();
     match head {
         head::Head::Branch(branch) => {
-            head::update_branch(&branch, &commit_hash.clone());
-            commands::restore::restore(vec![], Some(commit_hash.clone()), true, true)
+            head::update_branch(&branch, &commit_hash.clone(), &format!("merge {}: Fast-forward", name));
+            let blobs = Commit::load(&commit_hash).get_tree().get_recursive_blobs();
+            restore_worktree(None, &blobs);
+            restore_index(None, &blobs);
         }
         head::Head::Detached(_) => {
             // 相当于切换到了commit_hash，什么都没有发生
@@ -55,34 +72,427 @@ fn merge_ff(commit_hash: String) -> Result<(), MergeErr> {
     Ok(())
 }
 
-/** merge，暂时只支持fast forward */
-pub fn merge(branch: String) {
-    let merge_commit = {
-        if head::list_local_branches().contains(&branch) {
-            // Branch Name, e.g. master
-            head::get_branch_head(&branch)
+/// .mit下的合并状态文件：MERGE_HEAD（正在合并的commit）、MERGE_MSG、ORIG_HEAD（合并前的HEAD）
+fn merge_state_file(name: &str) -> PathBuf {
+    util::get_storage_path().unwrap().join(name)
+}
+
+/// 正在进行的合并的目标commit，MERGE_HEAD不存在时为None
+pub fn merge_head() -> Option<Hash> {
+    fs::read_to_string(merge_state_file("MERGE_HEAD"))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// 合并冲突后预设的提交信息
+pub fn merge_msg() -> Option<String> {
+    fs::read_to_string(merge_state_file("MERGE_MSG")).ok()
+}
+
+/// 合并完成（commit）或放弃（--abort）后清理状态文件
+pub fn clear_merge_state() {
+    for name in ["MERGE_HEAD", "MERGE_MSG", "ORIG_HEAD"] {
+        let _ = fs::remove_file(merge_state_file(name));
+    }
+}
+
+/// `-X`：冲突区块自动选择某一方的内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StrategyOption {
+    Ours,
+    Theirs,
+}
+
+/// 冲突区块的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resolve {
+    /// 写入冲突标记
+    Markers,
+    Ours,
+    Theirs,
+    /// 依次保留双方的行
+    Union,
+}
+
+impl From<Option<StrategyOption>> for Resolve {
+    fn from(favor: Option<StrategyOption>) -> Self {
+        match favor {
+            None => Resolve::Markers,
+            Some(StrategyOption::Ours) => Resolve::Ours,
+            Some(StrategyOption::Theirs) => Resolve::Theirs,
+        }
+    }
+}
+
+/** 文件内容合并：冲突标签、`-X ours|theirs`，以及.mitattributes中 `merge=<driver>` 指定的driver
+<br>内置driver：text(默认)、union、ours、theirs、binary(`-merge`，不做行级合并)；
+其他driver为配置 `merge.<driver>.driver` 中的外部命令，`%O %A %B` 替换为base、ours、theirs的临时文件，
+`%P` 替换为路径，命令将结果写回`%A`，退出码非0表示有冲突
+ */
+pub struct FileMerger<'a> {
+    /// 冲突标记的标签，e.g. `<<<<<<< HEAD`、`>>>>>>> feature`
+    pub ours: &'a str,
+    pub theirs: &'a str,
+    pub favor: Option<StrategyOption>,
+    attributes: Attributes,
+}
+
+impl<'a> FileMerger<'a> {
+    pub fn new(ours: &'a str, theirs: &'a str, favor: Option<StrategyOption>) -> FileMerger<'a> {
+        FileMerger { ours, theirs, favor, attributes: Attributes::load() }
+    }
+
+    /// 合并文件内容，返回 (合并结果, 是否有冲突)
+    fn merge(&self, path: &Path, base: &str, ours: &str, theirs: &str) -> (String, bool) {
+        match self.attributes.get(path, "merge").as_deref() {
+            None | Some("set") | Some("text") => self.merge_text(base, ours, theirs, self.favor.into()),
+            Some("union") => self.merge_text(base, ours, theirs, Resolve::Union),
+            Some("ours") => (ours.to_string(), false),
+            Some("theirs") => (theirs.to_string(), false),
+            Some("unset") | Some("binary") => match self.favor {
+                Some(StrategyOption::Theirs) => (theirs.to_string(), false),
+                favor => (ours.to_string(), favor.is_none()),
+            },
+            Some(driver) => match config::get(&format!("merge.{}.driver", driver)) {
+                Some(command) => self.merge_external(&command, path, base, ours, theirs),
+                None => self.merge_text(base, ours, theirs, self.favor.into()), // 未定义的driver按text处理
+            },
+        }
+    }
+
+    /// 文本的行级三路合并；配置 `merge.conflictStyle = diff3` 时冲突标记中额外输出base版本
+    fn merge_text(&self, base: &str, ours: &str, theirs: &str, resolve: Resolve) -> (String, bool) {
+        let diff3_style = config::get("merge.conflictstyle").is_some_and(|style| style == "diff3");
+        let mut lines: Vec<String> = Vec::new();
+        let mut conflicted = false;
+        for chunk in diff::merge3(base, ours, theirs, &DiffOptions::from_config()) {
+            match chunk {
+                MergeChunk::Clean(clean) => lines.extend(clean),
+                MergeChunk::Conflict { base, ours, theirs } => match resolve {
+                    Resolve::Ours => lines.extend(ours),
+                    Resolve::Theirs => lines.extend(theirs),
+                    Resolve::Union => {
+                        lines.extend(ours);
+                        lines.extend(theirs);
+                    }
+                    Resolve::Markers => {
+                        conflicted = true;
+                        lines.push(format!("<<<<<<< {}", self.ours));
+                        lines.extend(ours);
+                        if diff3_style {
+                            lines.push("||||||| base".to_string());
+                            lines.extend(base);
+                        }
+                        lines.push("=======".to_string());
+                        lines.extend(theirs);
+                        lines.push(format!(">>>>>>> {}", self.theirs));
+                    }
+                },
+            }
+        }
+        let mut content = lines.join("\n");
+        let trailing_newline = if ours.is_empty() {
+            theirs.ends_with('\n')
         } else {
-            // Commit Hash, e.g. a1b2c3d4
-            let store = store::Store::new();
-            let commit = store.search(&branch);
-            if commit.is_none() || !util::is_typeof_commit(commit.clone().unwrap()) {
-                println!("fatal: 非法的 commit hash: '{}'", branch);
-                return;
+            ours.ends_with('\n') || conflicted
+        };
+        if !content.is_empty() && trailing_newline {
+            content.push('\n');
+        }
+        (content, conflicted)
+    }
+
+    /// 调用外部merge driver
+    fn merge_external(&self, command: &str, path: &Path, base: &str, ours: &str, theirs: &str) -> (String, bool) {
+        let storage = util::get_storage_path().unwrap();
+        let files: Vec<PathBuf> = ["O", "A", "B"]
+            .iter()
+            .map(|k| storage.join(format!(".merge_file_{}", k)))
+            .collect();
+        for (file, content) in files.iter().zip([base, ours, theirs]) {
+            fs::write(file, content).unwrap();
+        }
+        // sh下路径作为位置参数传入，不拼接进命令，避免路径中的特殊字符被shell解释
+        let placeholders = ["%O", "%A", "%B", "%P"];
+        let paths: Vec<PathBuf> = files.iter().cloned().chain([path.to_path_buf()]).collect();
+        let mut command = command.replace("%L", "7");
+        let mut shell = if cfg!(windows) {
+            for (placeholder, path) in placeholders.iter().zip(&paths) {
+                command = command.replace(placeholder, &format!("\"{}\"", path.display()));
+            }
+            let mut cmd = Command::new("cmd");
+            cmd.arg("/C").arg(&command);
+            cmd
+        } else {
+            for (i, placeholder) in placeholders.iter().enumerate() {
+                command = command.replace(placeholder, &format!("\"${}\"", i + 1));
+            }
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(&command).arg("mit").args(&paths);
+            cmd
+        };
+        let status = shell.current_dir(util::get_working_dir().unwrap()).status();
+        let content = fs::read_to_string(&files[1]).unwrap_or_default();
+        files.iter().for_each(|file| {
+            let _ = fs::remove_file(file);
+        });
+        match status {
+            Ok(status) => (content, !status.success()),
+            Err(err) => {
+                println!("error: failed to execute merge driver '{}': {}", command, err);
+                (ours.to_string(), true)
             }
-            commit.unwrap()
         }
+    }
+}
+
+/// 合并冲突的文件：写入工作区的内容及各方版本
+pub struct Conflict {
+    pub path: PathBuf,
+    pub content: String,
+    pub stages: ConflictStages,
+}
+
+/// 三棵树的合并结果，blobs中也包含冲突文件（保留存在的一方，以便写入index）
+pub struct TreeMerge {
+    pub blobs: Vec<(PathBuf, Hash)>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// 逐个文件合并三棵树（相对workdir的路径）
+pub fn merge_trees(
+    base: &[(PathBuf, Hash)],
+    ours: &[(PathBuf, Hash)],
+    theirs: &[(PathBuf, Hash)],
+    merger: &FileMerger,
+) -> TreeMerge {
+    let to_map = |blobs: &[(PathBuf, Hash)]| blobs.iter().cloned().collect::<HashMap<PathBuf, Hash>>();
+    let (base, ours, theirs) = (to_map(base), to_map(ours), to_map(theirs));
+    let paths: BTreeSet<&PathBuf> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+
+    let mut result = TreeMerge { blobs: Vec::new(), conflicts: Vec::new() };
+    for path in paths {
+        let (b, o, t) = (base.get(path), ours.get(path), theirs.get(path));
+        let stages = ConflictStages { base: b.cloned(), ours: o.cloned(), theirs: t.cloned() };
+        let merged = if o == t || b == t {
+            o.cloned()
+        } else if b == o {
+            t.cloned()
+        } else if let (Some(o), Some(t)) = (o, t) {
+            // 双方都修改了文件（或都新增了同名文件）
+            let base_content = b.map(|h| Blob::load(h).get_content()).unwrap_or_default();
+            let ours_content = Blob::load(o).get_content();
+            let theirs_content = Blob::load(t).get_content();
+            println!("Auto-merging {}", display_path(path));
+            let (content, conflicted) = merger.merge(path, &base_content, &ours_content, &theirs_content);
+            if !conflicted {
+                Some(Blob::new(content).get_hash())
+            } else {
+                println!("CONFLICT (content): Merge conflict in {}", display_path(path));
+                result.blobs.push((path.clone(), o.clone()));
+                result.conflicts.push(Conflict { path: path.clone(), content, stages });
+                continue;
+            }
+        } else {
+            // 一方修改、另一方删除：工作区保留修改的一方
+            let (kept, deleted_by) = match (o, t) {
+                (Some(o), _) => (o, merger.theirs),
+                (_, Some(t)) => (t, merger.ours),
+                _ => unreachable!(),
+            };
+            println!(
+                "CONFLICT (modify/delete): {} deleted in {} and modified in the other",
+                display_path(path),
+                deleted_by
+            );
+            result.blobs.push((path.clone(), kept.clone()));
+            let content = Blob::load(kept).get_content();
+            result.conflicts.push(Conflict { path: path.clone(), content, stages });
+            continue;
+        };
+        if let Some(hash) = merged {
+            result.blobs.push((path.clone(), hash));
+        }
+    }
+    result
+}
+
+/// 将合并结果写入工作区和index，冲突文件写入冲突标记并记录stages
+pub fn apply_tree_merge(result: &TreeMerge) {
+    // 先恢复工作区（需要根据index判断文件是否已跟踪），再更新index
+    restore_worktree(None, &result.blobs);
+    restore_index(None, &result.blobs);
+    let index = Index::get_instance();
+    for conflict in &result.conflicts {
+        let path = conflict.path.to_absolute_workdir();
+        util::write_workfile(conflict.content.clone(), &path);
+        index.set_stages(&path, conflict.stages.clone());
+    }
+    index.save();
+}
+
+/// 将HEAD与commit三路合并的结果写入工作区和index
+fn merge_into_worktree(commit_hash: &Hash, name: &str, favor: Option<StrategyOption>) -> Result<TreeMerge, MergeErr> {
+    if !changes_to_be_staged().is_empty() || !changes_to_be_committed().is_empty() {
+        println!("fatal: 你有未提交的更改，无法合并");
+        return Err(MergeErr::NoClean);
+    }
+    let current_commit = head::current_head_commit();
+    let base_blobs = match merge_base::merge_base(&current_commit, commit_hash) {
+        Some(base) => Commit::load(&base).get_tree().get_recursive_blobs(),
+        None => Vec::new(),
     };
-    // 暂时只支持fast forward
-    let _ = merge_ff(merge_commit);
+    let ours_blobs = Commit::load(&current_commit).get_tree().get_recursive_blobs();
+    let theirs_blobs = Commit::load(commit_hash).get_tree().get_recursive_blobs();
+    let merger = FileMerger::new("HEAD", name, favor);
+    let result = merge_trees(&base_blobs, &ours_blobs, &theirs_blobs, &merger);
+    apply_tree_merge(&result);
+    Ok(result)
+}
+
+fn conflict_paths(result: TreeMerge) -> Vec<PathBuf> {
+    result.conflicts.into_iter().map(|c| c.path).collect()
+}
+
+/** 三路合并：合并base、HEAD、target三棵树，无冲突时生成有两个parent的merge commit
+<br>有冲突时写入冲突标记和MERGE_HEAD，等待用户解决后 `merge --continue` 或 `commit`
+ */
+fn merge_three_way(
+    commit_hash: &Hash,
+    name: &str,
+    message: String,
+    favor: Option<StrategyOption>,
+) -> Result<Hash, MergeErr> {
+    let current_commit = head::current_head_commit();
+    let result = merge_into_worktree(commit_hash, name, favor)?;
+    if !result.conflicts.is_empty() {
+        fs::write(merge_state_file("MERGE_HEAD"), commit_hash).unwrap();
+        fs::write(merge_state_file("MERGE_MSG"), &message).unwrap();
+        fs::write(merge_state_file("ORIG_HEAD"), &current_commit).unwrap();
+        return Err(MergeErr::Conflict(conflict_paths(result)));
+    }
+    let mut commit = Commit::new(Index::get_instance(), vec![current_commit, commit_hash.clone()], message);
+    let hash = commit.save();
+    head::update_head_commit(&hash, &format!("merge {}: Merge made by the 'ort' strategy.", name));
+    println!("Merge made by the 'ort' strategy.");
+    Ok(hash)
+}
+
+/// --squash：合并结果只写入工作区和暂存区，不提交，也不记录MERGE_HEAD，之后的commit只有一个parent
+fn merge_squash(commit_hash: &Hash, name: &str, favor: Option<StrategyOption>) -> Result<(), MergeErr> {
+    let result = merge_into_worktree(commit_hash, name, favor)?;
+    println!("Squash commit -- not updating HEAD");
+    if result.conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(MergeErr::Conflict(conflict_paths(result)))
+    }
+}
+
+/// 放弃合并，恢复到合并前的状态
+fn merge_abort() {
+    if merge_head().is_none() {
+        println!("fatal: There is no merge to abort (MERGE_HEAD missing).");
+        return;
+    }
+    reset_to_commit(&head::current_head_commit());
+    clear_merge_state();
+}
+
+/// 冲突解决（并add）后完成合并
+fn merge_continue() {
+    if merge_head().is_none() {
+        println!("fatal: There is no merge in progress (MERGE_HEAD missing).");
+        return;
+    }
+    let message = merge_msg().unwrap_or_else(|| "Merge".to_string());
+    commands::commit(message, true);
+}
+
+/// merge的可选行为
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// 即使可以fast forward也生成merge commit
+    pub no_ff: bool,
+    /// 只允许fast forward，否则放弃合并
+    pub ff_only: bool,
+    /// 将合并结果写入暂存区，但不提交
+    pub squash: bool,
+    /// merge commit的提交信息，默认 "Merge branch '<branch>'"
+    pub message: Option<String>,
+    /// `-X ours|theirs`
+    pub strategy_option: Option<StrategyOption>,
+}
+
+/** merge：能fast forward时直接快进，否则进行三路合并 */
+pub fn merge(branch: Option<String>, abort: bool, continue_merge: bool, options: MergeOptions) {
+    util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
+    if abort {
+        return merge_abort();
+    }
+    if continue_merge {
+        return merge_continue();
+    }
+    if merge_head().is_some() {
+        println!("fatal: You have not concluded your merge (MERGE_HEAD exists).");
+        println!("Please, commit your changes before you merge.");
+        return;
+    }
+    let branch = branch.expect("merge需要指定分支");
+    // Branch Name, e.g. master | Commit Hash, e.g. a1b2c3d4 | HEAD~2 ...
+    let merge_commit = match revision::resolve_commit(&branch) {
+        Ok(commit) => commit,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+    let current_commit = head::current_head_commit();
+    if !current_commit.is_empty() && check_ff(&merge_commit, current_commit.clone()).is_ok() {
+        println!("Already up to date.");
+        return;
+    }
+    if current_commit.is_empty() && (options.no_ff || options.squash) {
+        // 未出生的分支只能快进
+        if options.squash {
+            println!("fatal: Squash commit into empty head not supported yet");
+        } else {
+            println!("fatal: Non-fast-forward commit does not make sense into an empty head");
+        }
+        return;
+    }
+    let can_ff = check_ff(&current_commit, merge_commit.clone()).is_ok();
+    if options.ff_only && !can_ff {
+        println!("fatal: Not possible to fast-forward, aborting.");
+        return;
+    }
+    let result = if options.squash {
+        merge_squash(&merge_commit, &branch, options.strategy_option)
+    } else if can_ff && !options.no_ff {
+        merge_ff(merge_commit, &branch)
+    } else {
+        let message = options.message.unwrap_or_else(|| format!("Merge branch '{}'", branch));
+        merge_three_way(&merge_commit, &branch, message, options.strategy_option).map(|_| ())
+    };
+    if let Err(MergeErr::Conflict(paths)) = result {
+        for path in &paths {
+            println!("\t{}", display_path(path));
+        }
+        println!("Automatic merge failed; fix conflicts and then commit the result.");
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::{
-        commands::{commit, switch::switch},
+        commands::{self as cmd, commit, switch::switch},
         utils::test,
     };
+    use std::{fs, path::Path};
 
     #[test]
     fn test_check_ff() {
@@ -97,6 +507,8 @@ mod test {
         let new_branch = "new_branch".to_string();
         switch(None, Some(new_branch.clone()), false);
         commit::commit("new_branch commit 1".to_string(), true);
+        test::ensure_file(Path::new("ff.txt"), Some("ff"));
+        cmd::add(vec![], true, false);
         commit::commit("new_branch commit 2".to_string(), true);
         assert_ne!(head::current_head_commit(), commit1);
         assert_eq!(head::get_branch_head(&origin_branch.clone()), commit1);
@@ -107,17 +519,219 @@ mod test {
         switch(Some(origin_branch.clone()), None, false);
         assert_eq!(head::current_head_commit(), commit1);
 
-        let result = merge_ff(commit2.clone());
+        let result = merge_ff(commit2.clone(), &new_branch);
         assert!(result.is_ok());
         assert_eq!(head::current_head_commit(), commit2);
         assert_eq!(head::get_branch_head(&origin_branch.clone()), commit2);
+        assert!(Path::new("ff.txt").exists());
+        assert!(changes_to_be_committed().is_empty());
         println!("[info] success merge ff");
 
         // test no fast forward
         commit::commit("master commit 2".to_string(), true);
-        let result = merge_ff(commit1.clone());
+        let result = merge_ff(commit1.clone(), "HEAD~1");
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), MergeErr::NoFastForward));
         print!("success detect no fast forward");
     }
+
+    #[test]
+    fn test_three_way_merge() {
+        test::setup_with_empty_workdir();
+        test::ensure_file(Path::new("a.txt"), Some("1\n2\n3\n4\n5\n"));
+        test::ensure_file(Path::new("b.txt"), Some("b\n"));
+        cmd::add(vec![], true, false);
+        commit::commit("base".to_string(), false);
+
+        switch(None, Some("feature".to_string()), false);
+        test::ensure_file(Path::new("a.txt"), Some("one\n2\n3\n4\n5\n"));
+        test::ensure_file(Path::new("c.txt"), Some("c\n"));
+        cmd::add(vec![], true, false);
+        commit::commit("feature".to_string(), false);
+        let feature = head::current_head_commit();
+
+        switch(Some("master".to_string()), None, false);
+        test::ensure_file(Path::new("a.txt"), Some("1\n2\n3\n4\nfive\n"));
+        fs::remove_file("b.txt").unwrap();
+        cmd::add(vec![], true, false);
+        commit::commit("master".to_string(), false);
+        let master = head::current_head_commit();
+
+        merge(Some("feature".to_string()), false, false, MergeOptions::default());
+        let merged = Commit::load(&head::current_head_commit());
+        assert_eq!(merged.get_parent_hash(), vec![master, feature]);
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "one\n2\n3\n4\nfive\n");
+        assert!(Path::new("c.txt").exists());
+        assert!(!Path::new("b.txt").exists());
+        assert!(changes_to_be_committed().is_empty());
+
+        // 双方修改同一行：冲突，HEAD不变
+        switch(None, Some("other".to_string()), false);
+        test::ensure_file(Path::new("a.txt"), Some("one\n2\nthree\n4\nfive\n"));
+        cmd::add(vec![], true, false);
+        commit::commit("other".to_string(), false);
+        switch(Some("master".to_string()), None, false);
+        test::ensure_file(Path::new("a.txt"), Some("one\n2\nTHREE\n4\nfive\n"));
+        cmd::add(vec![], true, false);
+        commit::commit("conflict".to_string(), false);
+        let head = head::current_head_commit();
+        let other = head::get_branch_head(&"other".to_string());
+        let result = merge_three_way(&other, "other", "Merge branch 'other'".to_string(), None);
+        assert!(matches!(result, Err(MergeErr::Conflict(paths)) if paths == vec![PathBuf::from("a.txt")]));
+        assert_eq!(head::current_head_commit(), head);
+        assert_eq!(
+            fs::read_to_string("a.txt").unwrap(),
+            "one\n2\n<<<<<<< HEAD\nTHREE\n=======\nthree\n>>>>>>> other\n4\nfive\n"
+        );
+        let conflicts = Index::get_instance().get_conflicts();
+        assert_eq!(conflicts.len(), 1);
+        let stages = &conflicts[0].1;
+        assert!(stages.base.is_some() && stages.ours.is_some() && stages.theirs.is_some());
+        assert_eq!(merge_head(), Some(other.clone()));
+        assert_eq!(fs::read_to_string(merge_state_file("ORIG_HEAD")).unwrap(), head);
+        cmd::status();
+
+        // 有冲突时不能提交
+        commit::commit("resolve".to_string(), false);
+        assert_eq!(head::current_head_commit(), head);
+
+        // abort 恢复到合并前
+        merge(None, true, false, MergeOptions::default());
+        assert!(merge_head().is_none());
+        assert!(!merge_state_file("ORIG_HEAD").exists());
+        assert!(Index::get_instance().get_conflicts().is_empty());
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "one\n2\nTHREE\n4\nfive\n");
+        assert!(changes_to_be_staged().is_empty() && changes_to_be_committed().is_empty());
+
+        // 解决冲突后 --continue
+        merge(Some("other".to_string()), false, false, MergeOptions::default());
+        assert!(merge_head().is_some());
+        test::ensure_file(Path::new("a.txt"), Some("one\n2\nThree\n4\nfive\n"));
+        cmd::add(vec!["a.txt".to_string()], false, false);
+        assert!(Index::get_instance().get_conflicts().is_empty());
+        merge(None, false, true, MergeOptions::default());
+        let merged = Commit::load(&head::current_head_commit());
+        assert_eq!(merged.get_parent_hash(), vec![head, other]);
+        assert_eq!(merged.get_message(), "Merge branch 'other'");
+        assert!(merge_head().is_none());
+        assert!(!merge_state_file("ORIG_HEAD").exists());
+    }
+
+    #[test]
+    fn test_merge_modes() {
+        test::setup_with_empty_workdir();
+        test::ensure_file(Path::new("a.txt"), Some("a\n"));
+        cmd::add(vec![], true, false);
+        commit::commit("base".to_string(), false);
+        let base = head::current_head_commit();
+        switch(None, Some("feature2".to_string()), false);
+        test::ensure_file(Path::new("c.txt"), Some("c\n"));
+        cmd::add(vec![], true, false);
+        commit::commit("feature2".to_string(), false);
+        switch(Some("master".to_string()), None, false);
+        switch(None, Some("feature".to_string()), false);
+        test::ensure_file(Path::new("b.txt"), Some("b\n"));
+        cmd::add(vec![], true, false);
+        commit::commit("feature".to_string(), false);
+        let feature = head::current_head_commit();
+        switch(Some("master".to_string()), None, false);
+
+        // --no-ff：可以快进时也生成merge commit
+        let options = MergeOptions {
+            no_ff: true,
+            message: Some("merge feature".to_string()),
+            ..Default::default()
+        };
+        merge(Some("feature".to_string()), false, false, options);
+        let merged = Commit::load(&head::current_head_commit());
+        assert_eq!(merged.get_parent_hash(), vec![base, feature]);
+        assert_eq!(merged.get_message(), "merge feature");
+        assert!(Path::new("b.txt").exists());
+
+        // --ff-only：已分叉时拒绝
+        let head = head::current_head_commit();
+        let options = MergeOptions { ff_only: true, ..Default::default() };
+        merge(Some("feature2".to_string()), false, false, options);
+        assert_eq!(head::current_head_commit(), head);
+        assert!(!Path::new("c.txt").exists());
+
+        // --squash：只暂存，不提交
+        let options = MergeOptions { squash: true, ..Default::default() };
+        merge(Some("feature2".to_string()), false, false, options);
+        assert_eq!(head::current_head_commit(), head);
+        assert!(merge_head().is_none());
+        assert_eq!(changes_to_be_committed().new, vec![PathBuf::from("c.txt")]);
+        commit::commit("squashed".to_string(), false);
+        assert_eq!(Commit::load(&head::current_head_commit()).get_parent_hash(), vec![head]);
+
+        // 未出生的分支：--no-ff、--squash 拒绝，默认快进
+        let master = head::current_head_commit();
+        cmd::switch::switch_orphan("gh".to_string());
+        merge(Some("master".to_string()), false, false, MergeOptions { no_ff: true, ..Default::default() });
+        merge(Some("master".to_string()), false, false, MergeOptions { squash: true, ..Default::default() });
+        assert!(head::current_head_commit().is_empty());
+        assert!(changes_to_be_committed().is_empty());
+        merge(Some("master".to_string()), false, false, Default::default());
+        assert_eq!(head::get_branch_head(&"gh".to_string()), master);
+        assert!(Path::new("c.txt").exists());
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        test::setup_with_empty_workdir();
+        // 路径中的特殊字符不应被shell解释
+        let files = ["a.txt", "b.txt", "CHANGELOG.md", "gen \"$(id)'.lock", "data.bin"];
+        for file in files {
+            test::ensure_file(Path::new(file), Some("base\n"));
+        }
+        test::ensure_file(
+            Path::new(".mitattributes"),
+            Some("CHANGELOG.md merge=union\n*.lock merge=regen\n*.bin -merge\n"),
+        );
+        cmd::add(vec![], true, false);
+        commit::commit("base".to_string(), false);
+        let mut config = config::Config::load();
+        config.set("merge.regen.driver", "echo %P > %A");
+        config.save();
+
+        switch(None, Some("feature".to_string()), false);
+        for file in files {
+            test::ensure_file(Path::new(file), Some("theirs\n"));
+        }
+        cmd::add(vec![], true, false);
+        commit::commit("feature".to_string(), false);
+        switch(Some("master".to_string()), None, false);
+        for file in files {
+            test::ensure_file(Path::new(file), Some("ours\n"));
+        }
+        cmd::add(vec![], true, false);
+        commit::commit("master".to_string(), false);
+        let head = head::current_head_commit();
+
+        // 不指定-X：普通文本冲突，union/driver自动解决，-merge保留ours并标记冲突
+        merge(Some("feature".to_string()), false, false, MergeOptions::default());
+        let conflicts: Vec<PathBuf> = Index::get_instance()
+            .get_conflicts()
+            .into_iter()
+            .map(|(p, _)| util::to_workdir_relative_path(&p))
+            .collect();
+        assert_eq!(conflicts, ["a.txt", "b.txt", "data.bin"].map(PathBuf::from));
+        assert_eq!(fs::read_to_string("CHANGELOG.md").unwrap(), "ours\ntheirs\n");
+        assert_eq!(fs::read_to_string(files[3]).unwrap().trim(), files[3]);
+        assert_eq!(fs::read_to_string("data.bin").unwrap(), "ours\n");
+        merge(None, true, false, MergeOptions::default());
+
+        for (favor, expected) in [(StrategyOption::Ours, "ours\n"), (StrategyOption::Theirs, "theirs\n")] {
+            let options = MergeOptions { strategy_option: Some(favor), ..Default::default() };
+            merge(Some("feature".to_string()), false, false, options);
+            assert!(merge_head().is_none());
+            assert_eq!(fs::read_to_string("a.txt").unwrap(), expected);
+            assert_eq!(fs::read_to_string("data.bin").unwrap(), expected);
+            assert_eq!(fs::read_to_string("CHANGELOG.md").unwrap(), "ours\ntheirs\n");
+            let blobs = Commit::load(&head).get_tree().get_recursive_blobs();
+            restore_worktree(None, &blobs);
+            restore_index(None, &blobs);
+            head::update_head_commit(&head, "reset: moving to HEAD");
+        }
+    }
 }
@@ -0,0 +1,182 @@
+use std::{
+    collections::{BinaryHeap, HashMap},
+    time::SystemTime,
+};
+
+use crate::{
+    models::{Commit, Hash},
+    utils::{revision, util},
+};
+
+/// 遍历时的标记：从哪一侧可达，或已确定不是最近公共祖先
+const PARENT1: u8 = 1;
+const PARENT2: u8 = 2;
+const STALE: u8 = 4;
+
+/// 带缓存的commit时间，避免重复加载对象
+#[derive(Default)]
+struct Walker {
+    dates: HashMap<Hash, SystemTime>,
+    parents: HashMap<Hash, Vec<Hash>>,
+}
+
+impl Walker {
+    fn load(&mut self, hash: &Hash) -> SystemTime {
+        if let Some(date) = self.dates.get(hash) {
+            return *date;
+        }
+        let commit = Commit::load(hash);
+        self.parents.insert(hash.clone(), commit.get_parent_hash());
+        self.dates.insert(hash.clone(), commit.get_time());
+        commit.get_time()
+    }
+
+    fn parents(&mut self, hash: &Hash) -> Vec<Hash> {
+        self.load(hash);
+        self.parents[hash].clone()
+    }
+
+    /** 按提交时间从新到旧遍历，标记从a、b各自可达的commit；同时可达的即公共祖先，
+    其祖先标记为STALE。队列中只剩STALE时结束，不需要遍历全部历史
+     */
+    fn paint_down_to_common(&mut self, a: &Hash, b: &Hash) -> Vec<Hash> {
+        let mut flags: HashMap<Hash, u8> = HashMap::new();
+        let mut queue: BinaryHeap<(SystemTime, Hash)> = BinaryHeap::new();
+        let mut result = Vec::new();
+        flags.insert(a.clone(), PARENT1);
+        *flags.entry(b.clone()).or_default() |= PARENT2;
+        queue.push((self.load(a), a.clone()));
+        queue.push((self.load(b), b.clone()));
+
+        while queue.iter().any(|(_, hash)| flags[hash] & STALE == 0) {
+            let (_, hash) = queue.pop().unwrap();
+            let mut flag = flags[&hash];
+            if flag & (PARENT1 | PARENT2) == PARENT1 | PARENT2 {
+                if flag & STALE == 0 && !result.contains(&hash) {
+                    result.push(hash.clone());
+                }
+                flag |= STALE;
+            }
+            for parent in self.parents(&hash) {
+                let parent_flag = flags.entry(parent.clone()).or_default();
+                if *parent_flag & flag == flag {
+                    continue;
+                }
+                *parent_flag |= flag;
+                queue.push((self.load(&parent), parent));
+            }
+        }
+        result
+    }
+}
+
+/** 计算两个commit的所有最近公共祖先(LCA)，按提交时间从新到旧排序
+<br>criss-cross合并的历史中可能有多个
+ */
+pub fn merge_bases(a: &Hash, b: &Hash) -> Vec<Hash> {
+    let mut walker = Walker::default();
+    let candidates = walker.paint_down_to_common(a, b);
+    // 时间戳不可靠时，候选之间可能存在祖先关系，需要去除
+    let mut bases: Vec<Hash> = Vec::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        let redundant = candidates
+            .iter()
+            .enumerate()
+            .any(|(j, other)| i != j && walker.paint_down_to_common(candidate, other).contains(candidate));
+        if !redundant {
+            bases.push(candidate.clone());
+        }
+    }
+    bases.sort_by_key(|hash| std::cmp::Reverse(walker.load(hash)));
+    bases
+}
+
+/// 第一个（最新的）最近公共祖先
+pub fn merge_base(a: &Hash, b: &Hash) -> Option<Hash> {
+    merge_bases(a, b).into_iter().next()
+}
+
+/// ancestor是否是descendant的祖先（包括相同）
+pub fn is_ancestor(ancestor: &Hash, descendant: &Hash) -> bool {
+    ancestor == descendant || merge_bases(ancestor, descendant).contains(ancestor)
+}
+
+/** 打印两个commit的最近公共祖先
+<br>--is-ancestor：不输出，通过退出码表示A是否是B的祖先(0: 是，1: 否)
+ */
+pub fn merge_base_cmd(commits: Vec<String>, all: bool, check_ancestor: bool) {
+    util::check_repo_exist();
+    let mut hashes = Vec::new();
+    for rev in &commits {
+        match revision::resolve_commit(rev) {
+            Ok(hash) => hashes.push(hash),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(128);
+            }
+        }
+    }
+    let (a, b) = (&hashes[0], &hashes[1]);
+    if check_ancestor {
+        std::process::exit(if is_ancestor(a, b) { 0 } else { 1 });
+    }
+    let bases = merge_bases(a, b);
+    if bases.is_empty() {
+        std::process::exit(1);
+    }
+    let count = if all { bases.len() } else { 1 };
+    bases.iter().take(count).for_each(|hash| println!("{}", hash));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands as cmd,
+        models::{head, Index},
+        utils::test,
+    };
+
+    fn merge_commit(parents: Vec<&Hash>, message: &str) -> Hash {
+        let parents = parents.into_iter().cloned().collect();
+        Commit::new(Index::get_instance(), parents, message.to_string()).save()
+    }
+
+    #[test]
+    fn test_merge_base() {
+        test::setup_with_empty_workdir();
+        cmd::commit("root".to_string(), true);
+        let root = head::current_head_commit();
+        cmd::commit("a1".to_string(), true);
+        let a1 = head::current_head_commit();
+        cmd::switch(Some(root.clone()), None, true);
+        cmd::commit("b1".to_string(), true);
+        let b1 = head::current_head_commit();
+
+        assert_eq!(merge_bases(&a1, &b1), vec![root.clone()]);
+        assert_eq!(merge_base(&a1, &a1), Some(a1.clone()));
+        assert!(is_ancestor(&root, &a1));
+        assert!(!is_ancestor(&a1, &root));
+        assert!(!is_ancestor(&a1, &b1));
+
+        // criss-cross：两个最近公共祖先
+        let m1 = merge_commit(vec![&a1, &b1], "m1");
+        let m2 = merge_commit(vec![&b1, &a1], "m2");
+        let mut bases = merge_bases(&m1, &m2);
+        bases.sort();
+        let mut expected = vec![a1.clone(), b1.clone()];
+        expected.sort();
+        assert_eq!(bases, expected);
+
+        // 多层菱形合并，逐条路径遍历会指数爆炸
+        let (mut x, mut y) = (m1, m2);
+        for i in 0..30 {
+            let next_x = merge_commit(vec![&x, &y], &format!("x{}", i));
+            let next_y = merge_commit(vec![&y, &x], &format!("y{}", i));
+            (x, y) = (next_x, next_y);
+        }
+        assert!(is_ancestor(&root, &x));
+        assert!(is_ancestor(&a1, &y));
+        assert!(!is_ancestor(&x, &y));
+    }
+}
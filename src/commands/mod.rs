@@ -2,19 +2,45 @@ pub mod add;
 pub use add::add;
 pub mod branch;
 pub use branch::branch;
+pub mod cherry_pick;
+pub use cherry_pick::cherry_pick;
 pub mod commit;
 pub use commit::commit;
+pub mod config;
+pub use config::config;
+pub mod diff;
+pub use diff::diff;
 pub mod init;
 pub use init::init;
 pub mod log;
 pub use log::log;
 pub mod merge;
 pub use merge::merge;
+pub mod merge_base;
+pub use merge_base::merge_base_cmd as merge_base;
+pub mod pack_refs;
+pub use pack_refs::pack_refs;
+pub mod rebase;
+pub use rebase::rebase;
+pub mod reflog;
+pub use reflog::reflog;
 pub mod remove;
 pub use remove::remove as rm;
+pub mod reset;
+pub use reset::reset;
 pub mod restore;
 pub use restore::restore;
+pub mod revert;
+pub use revert::revert;
+pub mod rev_list;
+pub use rev_list::rev_list;
+pub mod sequencer;
+pub mod show;
+pub use show::show;
+pub mod stash;
 pub mod status;
 pub use status::status;
 pub mod switch;
 pub use switch::switch;
+pub mod tag;
+pub use tag::tag;
@@ -0,0 +1,55 @@
+use crate::{models::head, utils::util};
+
+/** 将refs/下的tag（`--all`时包括分支）合并到 .mit/packed-refs，减少ref文件的数量 */
+pub fn pack_refs(all: bool) {
+    util::check_repo_exist();
+    head::pack_refs(all);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands as cmd,
+        models::packed_refs,
+        utils::{revision, test},
+    };
+
+    #[test]
+    fn test_pack_refs() {
+        test::setup_with_empty_workdir();
+        cmd::commit("first".to_string(), true);
+        let first = head::current_head_commit();
+        cmd::branch(Some("feature/a".to_string()), None, Default::default());
+        cmd::tag(Some("v1".to_string()), None, false, None, false, false, false);
+        let refs = util::get_storage_path().unwrap().join("refs");
+
+        // 默认只打包tag
+        pack_refs(false);
+        assert!(!refs.join("tags/v1").exists());
+        assert!(refs.join("heads/feature/a").exists());
+        assert_eq!(head::list_tags(), vec!["v1"]);
+        assert_eq!(head::get_tag("v1"), first);
+
+        pack_refs(true);
+        assert!(!refs.join("heads/feature").exists());
+        assert_eq!(head::list_local_branches(), vec!["feature/a", "master"]);
+        assert_eq!(revision::resolve_commit("feature/a").unwrap(), first);
+        assert_eq!(packed_refs::read().len(), 3);
+
+        // loose ref优先
+        cmd::commit("second".to_string(), true);
+        let second = head::current_head_commit();
+        assert_eq!(head::get_branch_head(&"master".to_string()), second);
+        assert_eq!(packed_refs::get("refs/heads/master"), Some(first.clone()));
+        assert_eq!(head::list_local_branches(), vec!["feature/a", "master"]);
+
+        // 删除时同时从packed-refs中删除
+        let options = cmd::branch::BranchOptions { delete: Some("feature/a".to_string()), ..Default::default() };
+        cmd::branch(None, None, options);
+        cmd::tag(Some("v1".to_string()), None, false, None, false, true, false);
+        assert_eq!(head::list_local_branches(), vec!["master"]);
+        assert!(head::list_tags().is_empty());
+        assert_eq!(packed_refs::read().len(), 1);
+    }
+}
@@ -0,0 +1,492 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    commands::{
+        diff::display_path,
+        merge_base,
+        restore::reset_to_commit,
+        rev_list::RevQuery,
+        sequencer::{self, Action},
+        status::{changes_to_be_committed, changes_to_be_staged},
+    },
+    models::{head, Commit, Hash, Index},
+    utils::{revision, util},
+};
+
+/// todo中每一行的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    /// 应用commit
+    Pick,
+    /// 应用commit，并使用该行后面的文字作为新的提交信息
+    Reword,
+    /// 合并到前一个commit，提交信息拼接
+    Squash,
+    /// 合并到前一个commit，只保留前一个的提交信息
+    Fixup,
+    /// 丢弃commit
+    Drop,
+}
+
+impl Command {
+    fn parse(name: &str) -> Option<Command> {
+        match name {
+            "pick" | "p" => Some(Command::Pick),
+            "reword" | "r" => Some(Command::Reword),
+            "squash" | "s" => Some(Command::Squash),
+            "fixup" | "f" => Some(Command::Fixup),
+            "drop" | "d" => Some(Command::Drop),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Pick => "pick",
+            Command::Reword => "reword",
+            Command::Squash => "squash",
+            Command::Fixup => "fixup",
+            Command::Drop => "drop",
+        }
+    }
+}
+
+/// todo中的一行：`<command> <commit> [text]`
+#[derive(Debug, Clone)]
+struct TodoItem {
+    command: Command,
+    commit: Hash,
+    /// reword的新提交信息，其他操作中为原提交信息的标题，仅供阅读
+    text: String,
+}
+
+impl TodoItem {
+    fn to_line(&self) -> String {
+        format!("{} {} {}", self.command.name(), self.commit, self.text)
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// 解析todo列表，忽略空行和`#`开头的注释；commit可以是任意revision
+fn parse_todo(content: &str) -> Result<Vec<TodoItem>, String> {
+    let mut items = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.splitn(3, char::is_whitespace);
+        let name = parts.next().unwrap();
+        let command = Command::parse(name).ok_or(format!("error: invalid command '{}' in line '{}'", name, line))?;
+        let rev = parts.next().ok_or(format!("error: missing commit in line '{}'", line))?;
+        let commit = revision::resolve_commit(rev).map_err(|e| format!("{}\nerror: invalid line '{}'", e, line))?;
+        let text = parts.next().unwrap_or_default().trim().to_string();
+        items.push(TodoItem { command, commit, text });
+    }
+    Ok(items)
+}
+
+/// squash / fixup 之前必须有被应用的commit
+fn check_todo(items: &[TodoItem]) -> Result<(), String> {
+    let first = items.iter().find(|item| item.command != Command::Drop);
+    match first.filter(|item| matches!(item.command, Command::Squash | Command::Fixup)) {
+        Some(item) => Err(format!("error: cannot '{}' without a previous commit", item.command.name())),
+        None => Ok(()),
+    }
+}
+
+/** 进行中的rebase，保存在 .mit/rebase-merge 目录：
+<br>head-name: 原分支名（detached时为空）；onto: 新的基础；orig-head: 开始前的commit
+<br>todo: 剩余的操作，冲突时第一行为当前操作；done: 已完成的操作
+ */
+struct RebaseState {
+    head_name: String,
+    onto: Hash,
+    orig_head: Hash,
+    todo: Vec<TodoItem>,
+    done: Vec<TodoItem>,
+}
+
+fn state_dir() -> PathBuf {
+    util::get_storage_path().unwrap().join("rebase-merge")
+}
+
+/// 是否有进行中的rebase
+pub fn in_progress() -> bool {
+    state_dir().exists()
+}
+
+fn read_state(name: &str) -> Option<String> {
+    fs::read_to_string(state_dir().join(name)).ok().map(|s| s.trim().to_string())
+}
+
+impl RebaseState {
+    /// 读取进行中的rebase，没有时为None；todo或done无法解析时为Err，指出出错的行
+    fn load() -> Option<Result<RebaseState, String>> {
+        let (head_name, orig_head) = Self::load_heads()?;
+        let onto = read_state("onto")?;
+        let lines = |name: &str| {
+            parse_todo(&read_state(name).unwrap_or_default())
+                .map_err(|err| format!("{}\nerror: could not parse {}", err, state_dir().join(name).display()))
+        };
+        let (todo, done) = match (lines("todo"), lines("done")) {
+            (Ok(todo), Ok(done)) => (todo, done),
+            (Err(err), _) | (_, Err(err)) => return Some(Err(err)),
+        };
+        Some(Ok(RebaseState { head_name, onto, orig_head, todo, done }))
+    }
+
+    /// 读取进行中的rebase并打印错误，用于 --continue / --skip
+    fn load_or_report() -> Option<RebaseState> {
+        match Self::load() {
+            Some(Ok(state)) => Some(state),
+            Some(Err(err)) => {
+                println!("{}", err);
+                println!("hint: fix the todo list, or use 'mit rebase --abort'");
+                None
+            }
+            None => {
+                println!("error: No rebase in progress?");
+                None
+            }
+        }
+    }
+
+    /// 只读取 (head-name, orig-head)，不解析todo，--abort 不受todo错误的影响
+    fn load_heads() -> Option<(String, Hash)> {
+        Some((read_state("head-name")?, read_state("orig-head")?))
+    }
+
+    fn save(&self) {
+        let dir = state_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let join = |items: &Vec<TodoItem>| items.iter().map(|item| item.to_line() + "\n").collect::<String>();
+        fs::write(dir.join("head-name"), &self.head_name).unwrap();
+        fs::write(dir.join("onto"), &self.onto).unwrap();
+        fs::write(dir.join("orig-head"), &self.orig_head).unwrap();
+        fs::write(dir.join("todo"), join(&self.todo)).unwrap();
+        fs::write(dir.join("done"), join(&self.done)).unwrap();
+    }
+
+    fn clear() {
+        let _ = fs::remove_dir_all(state_dir());
+    }
+
+    /// 当前操作已完成
+    fn advance(&mut self) {
+        let item = self.todo.remove(0);
+        self.done.push(item);
+    }
+}
+
+/** 以当前index提交todo中的一项，没有任何更改的pick / reword会被丢弃
+<br>squash / fixup 之前的commit都被丢弃时（HEAD仍是onto），没有可以修改的commit，按pick处理
+ */
+fn commit_item(item: &TodoItem, onto: &Hash) {
+    let original = Commit::load(&item.commit);
+    let head_commit = head::current_head_commit();
+    let amend = matches!(item.command, Command::Squash | Command::Fixup) && head_commit != *onto;
+    let (parents, message, author) = if amend {
+        // 修改前一个commit：沿用其parent和作者
+        let prev = Commit::load(&head_commit);
+        let message = match item.command {
+            Command::Squash => format!("{}\n\n{}", prev.get_message().trim_end(), original.get_message()),
+            _ => prev.get_message(),
+        };
+        (prev.get_parent_hash(), message, prev.get_author())
+    } else {
+        if changes_to_be_committed().is_empty() {
+            println!("dropping {} -- patch contents already upstream", sequencer::describe(&original));
+            return;
+        }
+        let message = match item.command {
+            Command::Reword if !item.text.is_empty() => item.text.clone(),
+            _ => original.get_message(),
+        };
+        (vec![head_commit], message, original.get_author())
+    };
+    let reason = format!("rebase ({}): {}", item.command.name(), message.lines().next().unwrap_or_default());
+    let index = Index::get_instance();
+    let mut commit = Commit::new(index, parents, message);
+    commit.set_author(author);
+    let commit_hash = commit.save();
+    head::update_head_commit(&commit_hash, &reason);
+    index.save();
+}
+
+/// 应用todo中的一项，冲突时返回冲突文件
+fn apply_item(item: &TodoItem, onto: &Hash) -> Vec<PathBuf> {
+    let commit = Commit::load(&item.commit);
+    let head_commit = head::current_head_commit();
+    if item.command == Command::Pick && commit.get_parent_hash().first() == Some(&head_commit) {
+        // parent就是当前HEAD，直接快进，保留原commit
+        reset_to_commit(&item.commit);
+        let subject = commit.get_message().lines().next().unwrap_or_default().to_string();
+        head::update_head_commit(&item.commit, &format!("rebase (pick): {}", subject));
+        return Vec::new();
+    }
+    let conflicts = sequencer::apply(Action::Pick, &item.commit, None);
+    if conflicts.is_empty() {
+        commit_item(item, onto);
+    }
+    conflicts
+}
+
+/// 依次处理todo，冲突时保存状态并停止；全部完成后更新原分支并切换回去
+fn run(mut state: RebaseState) {
+    while let Some(item) = state.todo.first().cloned() {
+        if item.command != Command::Drop {
+            let conflicts = apply_item(&item, &state.onto);
+            if !conflicts.is_empty() {
+                state.save();
+                println!("error: could not apply {}", sequencer::describe(&Commit::load(&item.commit)));
+                for path in &conflicts {
+                    println!("\t{}", display_path(path));
+                }
+                println!("hint: Resolve all conflicts manually, mark them as resolved with 'mit add <paths>',");
+                println!("hint: then run \"mit rebase --continue\".");
+                println!("hint: You can instead skip this commit: run \"mit rebase --skip\".");
+                println!("hint: To abort and get back to the state before \"mit rebase\", run \"mit rebase --abort\".");
+                return;
+            }
+        }
+        state.advance();
+    }
+    let new_head = head::current_head_commit();
+    if state.head_name.is_empty() {
+        println!("Successfully rebased, HEAD is now at {}.", &new_head[..7]);
+    } else {
+        let branch = format!("refs/heads/{}", state.head_name);
+        head::update_branch(&state.head_name, &new_head, &format!("rebase (finish): {} onto {}", branch, state.onto));
+        head::change_head_to_branch(&state.head_name, &format!("rebase (finish): returning to {}", branch));
+        println!("Successfully rebased and updated refs/heads/{}.", state.head_name);
+    }
+    RebaseState::clear();
+}
+
+fn rebase_continue() {
+    let Some(mut state) = RebaseState::load_or_report() else {
+        return;
+    };
+    if !Index::get_instance().get_conflicts().is_empty() {
+        println!("error: Committing is not possible because you have unmerged files.");
+        return;
+    }
+    if let Some(item) = state.todo.first() {
+        // 用户已经手动commit，或解决冲突后没有任何更改时，不再提交
+        if !changes_to_be_committed().is_empty() {
+            commit_item(item, &state.onto);
+        }
+        state.advance();
+    }
+    run(state);
+}
+
+fn rebase_skip() {
+    let Some(mut state) = RebaseState::load_or_report() else {
+        return;
+    };
+    reset_to_commit(&head::current_head_commit());
+    if !state.todo.is_empty() {
+        state.todo.remove(0);
+    }
+    run(state);
+}
+
+fn rebase_abort() {
+    let Some((head_name, orig_head)) = RebaseState::load_heads() else {
+        return println!("error: No rebase in progress?");
+    };
+    reset_to_commit(&orig_head);
+    if head_name.is_empty() {
+        let reason = format!("rebase (abort): returning to {}", orig_head);
+        head::change_head_to_commit(&orig_head, &reason);
+    } else {
+        let reason = format!("rebase (abort): returning to refs/heads/{}", head_name);
+        head::change_head_to_branch(&head_name, &reason);
+    }
+    RebaseState::clear();
+}
+
+/// 默认的todo：upstream..HEAD 中除merge commit外的所有commit，从旧到新
+fn default_todo(upstream: &Hash) -> Vec<TodoItem> {
+    let query = RevQuery {
+        include: vec![head::current_head_commit()],
+        exclude: vec![upstream.clone()],
+    };
+    query
+        .run()
+        .into_iter()
+        .rev()
+        .filter(|commit| commit.get_parent_hash().len() <= 1)
+        .map(|commit| TodoItem {
+            command: Command::Pick,
+            commit: commit.get_hash(),
+            text: commit.get_message().lines().next().unwrap_or_default().to_string(),
+        })
+        .collect()
+}
+
+/** 将当前分支在upstream之后的commit依次应用到upstream上
+<br>--todo：从文件读取操作列表（pick / reword / squash / fixup / drop），代替默认的列表
+<br>冲突时停止，解决后 `--continue`，或 `--skip` 跳过当前commit，`--abort` 恢复到开始前的状态
+ */
+pub fn rebase(upstream: Option<String>, todo_file: Option<String>, continue_rebase: bool, skip: bool, abort: bool) {
+    util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
+    if continue_rebase {
+        return rebase_continue();
+    }
+    if skip {
+        return rebase_skip();
+    }
+    if abort {
+        return rebase_abort();
+    }
+    if in_progress() {
+        println!("error: a rebase is already in progress");
+        println!("hint: try \"mit rebase (--continue | --skip | --abort)\"");
+        return;
+    }
+    let orig_head = head::current_head_commit();
+    if orig_head.is_empty() {
+        return println!("fatal: no commits yet on the current branch");
+    }
+    if !changes_to_be_staged().is_empty() || !changes_to_be_committed().is_empty() {
+        println!("error: cannot rebase: You have unstaged or uncommitted changes.");
+        println!("hint: commit your changes or stash them to proceed.");
+        return;
+    }
+    let upstream = upstream.unwrap();
+    let onto = match revision::resolve_commit(&upstream) {
+        Ok(hash) => hash,
+        Err(err) => return println!("{}", err),
+    };
+    let todo = match todo_file {
+        Some(file) => match fs::read_to_string(&file) {
+            Ok(content) => match parse_todo(&content).and_then(|todo| check_todo(&todo).map(|_| todo)) {
+                Ok(todo) => todo,
+                Err(err) => return println!("{}", err),
+            },
+            Err(_) => return println!("fatal: could not read '{}'", file),
+        },
+        None => {
+            if merge_base::is_ancestor(&onto, &orig_head) {
+                return println!("Current branch is up to date.");
+            }
+            default_todo(&onto)
+        }
+    };
+    let head_name = match head::current_head() {
+        head::Head::Branch(name) => name,
+        head::Head::Detached(_) => String::new(),
+    };
+    // 在detached HEAD上逐个应用，完成后再更新原分支
+    reset_to_commit(&onto);
+    head::change_head_to_commit(&onto, &format!("rebase (start): checkout {}", upstream));
+    run(RebaseState { head_name, onto, orig_head, todo, done: Vec::new() });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{commands as cmd, utils::test};
+    use std::path::Path;
+
+    fn messages(count: usize) -> Vec<String> {
+        let mut hash = head::current_head_commit();
+        let mut result = Vec::new();
+        for _ in 0..count {
+            let commit = Commit::load(&hash);
+            result.push(commit.get_message());
+            hash = commit.get_parent_hash().first().cloned().unwrap_or_default();
+        }
+        result
+    }
+
+    #[test]
+    fn test_rebase() {
+        test::setup_with_empty_workdir();
+        let base = test::write_and_commit("a.txt", "1\n2\n3\n", "base");
+        cmd::switch(None, Some("feature".to_string()), false);
+        test::write_and_commit("a.txt", "one\n2\n3\n", "f1");
+        test::write_and_commit("b.txt", "b\n", "f2");
+        cmd::switch(Some("master".to_string()), None, false);
+        let master = test::write_and_commit("c.txt", "c\n", "m1");
+
+        cmd::switch(Some("feature".to_string()), None, false);
+        rebase(Some("master".to_string()), None, false, false, false);
+        assert!(!in_progress());
+        assert_eq!(messages(4), vec!["f2", "f1", "m1", "base"]);
+        assert!(matches!(head::current_head(), head::Head::Branch(name) if name == "feature"));
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "one\n2\n3\n");
+        assert!(Path::new("c.txt").exists());
+        let rebased = head::current_head_commit();
+
+        // 冲突：--abort恢复
+        cmd::switch(Some("master".to_string()), None, false);
+        test::write_and_commit("a.txt", "ONE\n2\n3\n", "m2");
+        cmd::switch(Some("feature".to_string()), None, false);
+        rebase(Some("master".to_string()), None, false, false, false);
+        assert!(in_progress());
+        assert!(fs::read_to_string("a.txt").unwrap().contains("<<<<<<< HEAD"));
+        rebase(None, None, false, false, true);
+        assert!(!in_progress());
+        assert_eq!(head::current_head_commit(), rebased);
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "one\n2\n3\n");
+
+        // 冲突后--continue；todo有错误时停止，不会静默完成
+        rebase(Some("master".to_string()), None, false, false, false);
+        test::ensure_file(Path::new("a.txt"), Some("One\n2\n3\n"));
+        cmd::add(vec![], true, false);
+        let todo_path = state_dir().join("todo");
+        let todo = fs::read_to_string(&todo_path).unwrap();
+        fs::write(&todo_path, todo.replacen("pick", "pikc", 1)).unwrap();
+        assert!(matches!(RebaseState::load(), Some(Err(err)) if err.contains("pikc")));
+        rebase(None, None, true, false, false);
+        assert!(in_progress());
+        assert!(RebaseState::load_heads().is_some());
+        fs::write(&todo_path, todo).unwrap();
+        rebase(None, None, true, false, false);
+        assert!(!in_progress());
+        assert_eq!(messages(4), vec!["f2", "f1", "m2", "m1"]);
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "One\n2\n3\n");
+
+        // todo文件：reword、squash、fixup、drop
+        cmd::switch(Some(base.clone()), None, true);
+        let t1 = test::write_and_commit("d.txt", "1\n", "t1");
+        let t2 = test::write_and_commit("d.txt", "1\n2\n", "t2");
+        let t3 = test::write_and_commit("d.txt", "1\n2\n3\n", "t3");
+        let t4 = test::write_and_commit("e.txt", "e\n", "t4");
+        let t5 = test::write_and_commit("f.txt", "f\n", "t5");
+        let todo =
+            format!("# comment\nreword {} first\nsquash {} t2\nfixup {}\ndrop {}\npick {}\n", t1, t2, t3, t4, &t5[..7]);
+        let todo_file = util::get_storage_path().unwrap().join("todo-list");
+        fs::write(&todo_file, todo).unwrap();
+        rebase(Some(master), Some(todo_file.to_string_lossy().to_string()), false, false, false);
+        assert!(!in_progress());
+        assert_eq!(messages(3), vec!["t5", "first\n\nt2", "m1"]);
+        assert_eq!(fs::read_to_string("d.txt").unwrap(), "1\n2\n3\n");
+        assert!(!Path::new("e.txt").exists());
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "1\n2\n3\n");
+
+        // 第一个pick已在upstream中被丢弃，之后的squash没有可修改的commit，按pick处理，不会修改onto
+        cmd::switch(Some("master".to_string()), None, false);
+        let upstream = test::write_and_commit("g.txt", "g\n", "u1");
+        cmd::switch(Some(base.clone()), None, true);
+        let x1 = test::write_and_commit("g.txt", "g\n", "x1");
+        let x2 = test::write_and_commit("h.txt", "h\n", "x2");
+        fs::write(&todo_file, format!("pick {}\nsquash {}\n", x1, x2)).unwrap();
+        rebase(Some("master".to_string()), Some(todo_file.to_string_lossy().to_string()), false, false, false);
+        assert!(!in_progress());
+        assert_eq!(messages(2), vec!["x2", "u1"]);
+        assert_eq!(Commit::load(&head::current_head_commit()).get_parent_hash(), vec![upstream]);
+        assert!(Path::new("h.txt").exists());
+
+        assert!(check_todo(&parse_todo(&format!("drop {}\nfixup {}", t2, t1)).unwrap()).is_err());
+        assert!(parse_todo(&format!("edit {}", t1)).is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use colored::Colorize;
+
+use crate::{
+    models::reflog,
+    utils::{revision, util},
+};
+
+/** 显示ref的更新记录，默认为HEAD，从新到旧，格式同git：`<hash> <ref>@{n}: <reason>` */
+pub fn reflog(ref_name: Option<String>) {
+    util::check_repo_exist();
+    let name = ref_name.unwrap_or("HEAD".to_string());
+    let entries = reflog::read(&revision::reflog_ref(&name));
+    for (i, entry) in entries.iter().enumerate() {
+        let hash = &entry.new[..7.min(entry.new.len())];
+        println!("{} {}@{{{}}}: {}", hash.yellow(), name, i, entry.message);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        commands as cmd,
+        models::{head, reflog},
+        utils::test,
+    };
+
+    #[test]
+    fn test_reflog() {
+        test::setup_with_empty_workdir();
+        cmd::commit("first".to_string(), true);
+        let first = head::current_head_commit();
+        cmd::switch(None, Some("dev".to_string()), false);
+        cmd::commit("second".to_string(), true);
+        cmd::reset(Some("HEAD~1".to_string()), cmd::reset::ResetMode::Soft, vec![]);
+
+        let messages: Vec<String> = reflog::read("HEAD").into_iter().map(|e| e.message).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "reset: moving to HEAD~1",
+                "commit: second",
+                "checkout: moving from master to dev",
+                "commit (initial): first",
+            ]
+        );
+        let branch = reflog::read("refs/heads/dev");
+        assert_eq!(branch.len(), 3); // 创建、commit、reset
+        assert_eq!(branch[2].message, "branch: Created from HEAD");
+        assert_eq!(branch[0].new, first);
+        cmd::reflog(Some("dev".to_string()));
+    }
+}
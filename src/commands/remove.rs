@@ -5,6 +5,9 @@ use std::{fs, io, path::PathBuf};
 /// 从暂存区&|工作区删除文件
 pub fn remove(files: Vec<String>, cached: bool, recursive: bool) -> io::Result<()> {
     util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return Ok(());
+    }
     let index = Index::get_instance();
     for file in files.iter() {
         let path = PathBuf::from(file);
@@ -0,0 +1,158 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    commands::{
+        diff::display_path,
+        merge,
+        restore::{restore_index, restore_worktree},
+        status::changes_to_be_staged,
+    },
+    models::{head, Commit, Index},
+    utils::{revision, util},
+};
+
+/// reset的模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResetMode {
+    /// 只移动HEAD（分支）
+    Soft,
+    /// 移动HEAD并重置暂存区
+    #[default]
+    Mixed,
+    /// 移动HEAD并重置暂存区和工作区
+    Hard,
+}
+
+/** 将当前分支（或detached HEAD）移动到指定commit，并按模式重置暂存区、工作区
+<br>指定paths时不移动HEAD，只将这些文件在暂存区中恢复为commit中的版本，即 `mit reset <rev> -- <paths>`
+ */
+pub fn reset(target: Option<String>, mode: ResetMode, paths: Vec<String>) {
+    util::check_repo_exist();
+    if (mode != ResetMode::Soft || !paths.is_empty()) && !util::check_worktree_exist() {
+        return; // 只有 --soft 不需要工作区
+    }
+    let target_name = target.clone().unwrap_or("HEAD".to_string());
+    let commit = match target {
+        Some(rev) => match revision::resolve_commit(&rev) {
+            Ok(hash) => hash,
+            Err(err) => return println!("{}", err),
+        },
+        None => head::current_head_commit(), // 没有commit时为空，即重置为空
+    };
+    let blobs = if commit.is_empty() {
+        Vec::new()
+    } else {
+        Commit::load(&commit).get_tree().get_recursive_blobs()
+    };
+
+    if !paths.is_empty() {
+        if mode != ResetMode::Mixed {
+            let mode = if mode == ResetMode::Soft { "soft" } else { "hard" };
+            return println!("fatal: Cannot do {} reset with paths.", mode);
+        }
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        restore_index(Some(&paths), &blobs);
+        return print_unstaged();
+    }
+
+    if mode != ResetMode::Soft {
+        // 先清理合并状态，再记录本次reset的ORIG_HEAD
+        merge::clear_merge_state();
+    }
+    let orig_head = head::current_head_commit();
+    if !orig_head.is_empty() {
+        fs::write(util::get_storage_path().unwrap().join("ORIG_HEAD"), &orig_head).unwrap();
+    }
+    if !commit.is_empty() {
+        head::update_head_commit(&commit, &format!("reset: moving to {}", target_name));
+    }
+    if mode == ResetMode::Soft {
+        return;
+    }
+    if mode == ResetMode::Hard {
+        // 工作区需要先于暂存区恢复：根据暂存区判断哪些文件已跟踪
+        restore_worktree(None, &blobs);
+    }
+    let index = Index::get_instance();
+    for (path, _) in index.get_conflicts() {
+        index.remove(&path);
+    }
+    restore_index(None, &blobs);
+    match mode {
+        ResetMode::Hard if !commit.is_empty() => {
+            let subject = Commit::load(&commit)
+                .get_message()
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            println!("HEAD is now at {} {}", &commit[..7], subject);
+        }
+        _ => print_unstaged(),
+    }
+}
+
+fn print_unstaged() {
+    let changes = changes_to_be_staged();
+    if changes.modified.is_empty() && changes.deleted.is_empty() {
+        return;
+    }
+    println!("Unstaged changes after reset:");
+    let mut lines: Vec<(String, &str)> = changes.modified.iter().map(|p| (display_path(p), "M")).collect();
+    lines.extend(changes.deleted.iter().map(|p| (display_path(p), "D")));
+    lines.sort();
+    for (path, kind) in lines {
+        println!("{}\t{}", kind, path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{commands as cmd, commands::status::changes_to_be_committed, utils::test};
+    use std::path::Path;
+
+    #[test]
+    fn test_reset() {
+        test::setup_with_empty_workdir();
+        let c1 = test::write_and_commit("a.txt", "1\n", "c1");
+        test::write_and_commit("b.txt", "b\n", "c2");
+        let c3 = test::write_and_commit("a.txt", "3\n", "c3");
+
+        // --soft：只移动分支，更改保留在暂存区
+        reset(Some(c1.clone()), ResetMode::Soft, vec![]);
+        assert_eq!(head::current_head_commit(), c1);
+        let staged = changes_to_be_committed();
+        assert_eq!(staged.new, vec![PathBuf::from("b.txt")]);
+        assert_eq!(staged.modified, vec![PathBuf::from("a.txt")]);
+
+        // --mixed：暂存区恢复，工作区保留
+        reset(Some(c3.clone()), ResetMode::Soft, vec![]);
+        reset(Some("HEAD~2".to_string()), ResetMode::Mixed, vec![]);
+        assert_eq!(head::current_head_commit(), c1);
+        assert!(changes_to_be_committed().is_empty());
+        assert_eq!(changes_to_be_staged().modified, vec![PathBuf::from("a.txt")]);
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "3\n");
+
+        // --hard：工作区也恢复，已跟踪的新文件被删除
+        reset(Some(c3.clone()), ResetMode::Mixed, vec![]);
+        cmd::add(vec![], true, false);
+        reset(Some(c1.clone()), ResetMode::Hard, vec![]);
+        assert_eq!(head::current_head_commit(), c1);
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "1\n");
+        assert!(!Path::new("b.txt").exists());
+        assert!(changes_to_be_staged().is_empty());
+
+        // 指定paths：只恢复暂存区，不移动HEAD
+        reset(Some(c3.clone()), ResetMode::Hard, vec![]);
+        test::ensure_file(Path::new("a.txt"), Some("new\n"));
+        test::ensure_file(Path::new("b.txt"), Some("new\n"));
+        cmd::add(vec![], true, false);
+        reset(None, ResetMode::Mixed, vec!["a.txt".to_string()]);
+        assert_eq!(head::current_head_commit(), c3);
+        assert_eq!(changes_to_be_committed().modified, vec![PathBuf::from("b.txt")]);
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "new\n");
+        reset(Some(c1), ResetMode::Hard, vec!["a.txt".to_string()]);
+        assert_eq!(head::current_head_commit(), c3);
+    }
+}
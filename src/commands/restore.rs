@@ -7,7 +7,7 @@ use std::{
 use crate::utils::path_ext::PathExt;
 use crate::{
     models::*,
-    utils::{store, util},
+    utils::{revision, util},
 };
 
 fn restore_to_file(hash: &Hash, path: &PathBuf) {
@@ -146,12 +146,31 @@ pub fn restore_index(filter: Option<&Vec<PathBuf>>, target_blobs: &[(PathBuf, Ha
     }
     index.save();
 }
+/// 将工作区和暂存区都恢复为commit的状态，丢弃所有更改和未解决的冲突（commit为空时恢复为空）
+pub fn reset_to_commit(commit_hash: &Hash) {
+    let blobs = if commit_hash.is_empty() {
+        Vec::new()
+    } else {
+        Commit::load(commit_hash).get_tree().get_recursive_blobs()
+    };
+    restore_worktree(None, &blobs);
+    // 冲突文件的hash可能与目标相同，先移除以清除stages
+    let index = Index::get_instance();
+    for (path, _) in index.get_conflicts() {
+        index.remove(&path);
+    }
+    restore_index(None, &blobs);
+}
+
 /**
 对于工作区中的新文件，若已跟踪，则删除；若未跟踪，则保留<br>
 对于暂存区中被删除的文件，同样会恢复<br>
 注意：不会删除空文件夹
  */
 pub fn restore(paths: Vec<String>, source: Option<String>, worktree: bool, staged: bool) {
+    if !util::check_worktree_exist() {
+        return;
+    }
     let paths = paths.iter().map(PathBuf::from).collect::<Vec<PathBuf>>();
     let target_commit: Hash = {
         match source {
@@ -168,18 +187,15 @@ pub fn restore(paths: Vec<String>, source: Option<String>, worktree: bool, stage
                 if src == "HEAD" {
                     //Default Source
                     head::current_head_commit() // "" if not exist
-                } else if head::list_local_branches().contains(src) {
-                    // Branch Name, e.g. master
-                    head::get_branch_head(src) // "" if not exist
                 } else {
-                    // [Commit Hash, e.g. a1b2c3d4] || [Wrong Branch Name]
-                    let store = store::Store::new();
-                    let commit = store.search(src);
-                    if commit.is_none() || !util::is_typeof_commit(commit.clone().unwrap()) {
-                        println!("fatal: 非法的 commit hash: '{}'", src);
-                        return;
+                    // [Branch Name, e.g. master] || [Commit Hash, e.g. a1b2c3d4] || HEAD~1 ...
+                    match revision::resolve_commit(src) {
+                        Ok(commit) => commit,
+                        Err(err) => {
+                            println!("{}", err);
+                            return;
+                        }
                     }
-                    commit.unwrap()
                 }
             }
         }
@@ -196,9 +212,9 @@ pub fn restore(paths: Vec<String>, source: Option<String>, worktree: bool, stage
             //从[target_commit]中恢复
             if target_commit.is_empty() {
                 //target_commit不存在 无法从目标恢复
-                if source.is_some() {
+                if let Some(source) = source {
                     // 如果指定了source，说明source解析失败，报错
-                    println!("fatal: could not resolve {}", source.unwrap());
+                    println!("fatal: could not resolve {}", source);
                     return;
                 }
                 Vec::new() //否则使用[空]来恢复 代表default status
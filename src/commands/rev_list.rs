@@ -0,0 +1,157 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    commands::merge_base,
+    models::{Commit, Hash},
+    utils::{
+        revision::{self, RevisionErr},
+        util,
+    },
+};
+
+/** rev-list查询：从include出发可达、但从exclude出发不可达的所有commit
+<br>`A..B` = `^A B`，`A...B` = `A B ^merge-base(A, B)`
+ */
+#[derive(Debug, Default, Clone)]
+pub struct RevQuery {
+    pub include: Vec<Hash>,
+    pub exclude: Vec<Hash>,
+}
+
+/// range的两端为空时默认为HEAD，e.g. `main..` = `main..HEAD`
+fn resolve_endpoint(rev: &str) -> Result<Hash, RevisionErr> {
+    revision::resolve_commit(if rev.is_empty() { "HEAD" } else { rev })
+}
+
+impl RevQuery {
+    /// 解析参数：`A`、`^A`、`A..B`、`A...B`，可以有多个；全部为空时默认为HEAD
+    pub fn parse<T: AsRef<str>>(args: &[T]) -> Result<RevQuery, RevisionErr> {
+        let mut query = RevQuery::default();
+        for arg in args {
+            let arg = arg.as_ref();
+            if arg.starts_with(":/") {
+                query.include.push(revision::resolve_commit(arg)?);
+            } else if let Some(rev) = arg.strip_prefix('^') {
+                query.exclude.push(revision::resolve_commit(rev)?);
+            } else if let Some((a, b)) = arg.split_once("...") {
+                let (a, b) = (resolve_endpoint(a)?, resolve_endpoint(b)?);
+                query.exclude.extend(merge_base::merge_bases(&a, &b));
+                query.include.extend([a, b]);
+            } else if let Some((a, b)) = arg.split_once("..") {
+                query.exclude.push(resolve_endpoint(a)?);
+                query.include.push(resolve_endpoint(b)?);
+            } else {
+                query.include.push(revision::resolve_commit(arg)?);
+            }
+        }
+        if args.is_empty() {
+            query.include.push(revision::resolve_commit("HEAD")?);
+        }
+        Ok(query)
+    }
+
+    /// 执行查询，按提交时间从新到旧排序
+    pub fn run(&self) -> Vec<Commit> {
+        let mut excluded: HashSet<Hash> = HashSet::new();
+        for hash in &self.exclude {
+            excluded.extend(ancestors(hash));
+        }
+        let mut commits = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue: VecDeque<Hash> = self.include.iter().cloned().collect();
+        while let Some(hash) = queue.pop_front() {
+            if excluded.contains(&hash) || !visited.insert(hash.clone()) {
+                continue;
+            }
+            let commit = Commit::load(&hash);
+            queue.extend(commit.get_parent_hash());
+            commits.push(commit);
+        }
+        commits.sort_by_key(|c| std::cmp::Reverse(c.get_time()));
+        commits
+    }
+}
+
+/// commit及其所有祖先
+pub fn ancestors(hash: &Hash) -> HashSet<Hash> {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([hash.clone()]);
+    while let Some(hash) = queue.pop_front() {
+        if visited.insert(hash.clone()) {
+            queue.extend(Commit::load(&hash).get_parent_hash());
+        }
+    }
+    visited
+}
+
+/// local相对upstream的 (ahead, behind)：只在local上的commit数、只在upstream上的commit数
+pub fn ahead_behind(local: &Hash, upstream: &Hash) -> (usize, usize) {
+    let local = ancestors(local);
+    let upstream = ancestors(upstream);
+    (local.difference(&upstream).count(), upstream.difference(&local).count())
+}
+
+/** 列出commit，默认打印hash；--count只打印数量
+<br>e.g. `mit rev-list --count main..feature` 查询feature上有、main上没有的提交数
+ */
+pub fn rev_list(revs: Vec<String>, count: bool, max_count: Option<usize>) {
+    util::check_repo_exist();
+    let query = match RevQuery::parse(&revs) {
+        Ok(query) => query,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+    let mut commits = query.run();
+    if let Some(max) = max_count {
+        commits.truncate(max);
+    }
+    if count {
+        println!("{}", commits.len());
+    } else {
+        commits.iter().for_each(|c| println!("{}", c.get_hash()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::{self as cmd},
+        models::head,
+        utils::test,
+    };
+
+    fn count(args: &[&str]) -> usize {
+        RevQuery::parse(args).unwrap().run().len()
+    }
+
+    #[test]
+    fn test_rev_list() {
+        test::setup_with_empty_workdir();
+        cmd::commit("base".to_string(), true);
+        cmd::commit("main 1".to_string(), true);
+        cmd::switch(None, Some("feature".to_string()), false);
+        cmd::commit("feature 1".to_string(), true);
+        cmd::commit("feature 2".to_string(), true);
+        cmd::commit("feature 3".to_string(), true);
+        cmd::switch(Some("master".to_string()), None, false);
+        cmd::commit("main 2".to_string(), true);
+
+        assert_eq!(count(&[]), 3);
+        assert_eq!(count(&["feature"]), 5);
+        assert_eq!(count(&["master..feature"]), 3);
+        assert_eq!(count(&["feature..master"]), 1);
+        assert_eq!(count(&["feature", "^master"]), 3);
+        assert_eq!(count(&["master...feature"]), 4);
+        assert_eq!(count(&["..feature"]), 3);
+        assert_eq!(count(&["master", "feature"]), 6);
+        assert_eq!(count(&["feature~2..feature"]), 2);
+
+        let commits = RevQuery::parse(&["master..feature"]).unwrap().run();
+        assert_eq!(commits[0].get_hash(), head::get_branch_head(&"feature".to_string()));
+        assert_eq!(commits[2].get_message(), "feature 1");
+        assert!(RevQuery::parse(&["nothing..master"]).is_err());
+    }
+}
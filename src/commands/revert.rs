@@ -0,0 +1,95 @@
+use crate::{
+    commands::sequencer::{self, Action, SequenceOptions},
+    models::Hash,
+    utils::util,
+};
+
+/// 正在revert的commit，REVERT_HEAD不存在时为None
+pub fn revert_head() -> Option<Hash> {
+    sequencer::current(Action::Revert)
+}
+
+/** 应用已有commit的反向更改，并生成新的commit，提交信息为 `Revert "<subject>"`
+<br>-m 撤销merge commit时以第几个parent为主线，即撤销该merge从其他parent带来的更改
+<br>-n 只应用到工作区和暂存区；冲突时停止，解决后 `--continue`，或 `--skip`、`--abort`
+ */
+pub fn revert(
+    commits: Vec<String>,
+    mainline: Option<usize>,
+    no_commit: bool,
+    continue_revert: bool,
+    skip: bool,
+    abort: bool,
+) {
+    util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
+    if continue_revert {
+        return sequencer::resume(Action::Revert);
+    }
+    if skip {
+        return sequencer::skip(Action::Revert);
+    }
+    if abort {
+        return sequencer::abort(Action::Revert);
+    }
+    let options = SequenceOptions { no_commit, record_origin: false, mainline };
+    sequencer::start(Action::Revert, commits, options);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::{self as cmd, merge::MergeOptions},
+        models::{head, Commit},
+        utils::test,
+    };
+    use std::{fs, path::Path};
+
+    #[test]
+    fn test_revert() {
+        test::setup_with_empty_workdir();
+        test::write_and_commit("a.txt", "1\n2\n3\n", "base");
+        let c1 = test::write_and_commit("a.txt", "one\n2\n3\n", "c1");
+        let c2 = test::write_and_commit("a.txt", "one\n2\nthree\n", "c2");
+
+        // 撤销较早的commit
+        revert(vec![c1.clone()], None, false, false, false, false);
+        let reverted = Commit::load(&head::current_head_commit());
+        assert_eq!(reverted.get_parent_hash(), vec![c2.clone()]);
+        assert_eq!(reverted.get_message(), format!("Revert \"c1\"\n\nThis reverts commit {}.", c1));
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "1\n2\nthree\n");
+
+        // 冲突：--abort恢复
+        let head = test::write_and_commit("a.txt", "1\n2\nTHREE\n", "c3");
+        revert(vec![c2.clone()], None, false, false, false, false);
+        assert_eq!(revert_head(), Some(c2.clone()));
+        assert!(fs::read_to_string("a.txt").unwrap().contains("<<<<<<< HEAD"));
+        revert(vec![], None, false, false, false, true);
+        assert!(revert_head().is_none());
+        assert_eq!(head::current_head_commit(), head);
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "1\n2\nTHREE\n");
+
+        // merge commit 需要 -m
+        cmd::switch(None, Some("feature".to_string()), false);
+        test::write_and_commit("b.txt", "b\n", "add b");
+        cmd::switch(Some("master".to_string()), None, false);
+        let options = MergeOptions { no_ff: true, ..Default::default() };
+        cmd::merge(Some("feature".to_string()), false, false, options);
+        let merge = head::current_head_commit();
+        assert_eq!(Commit::load(&merge).get_parent_hash().len(), 2);
+        revert(vec![merge.clone()], None, false, false, false, false);
+        assert_eq!(head::current_head_commit(), merge);
+
+        revert(vec![merge.clone()], Some(1), false, false, false, false);
+        let reverted = Commit::load(&head::current_head_commit());
+        assert_eq!(reverted.get_parent_hash(), vec![merge.clone()]);
+        assert!(reverted
+            .get_message()
+            .ends_with(&format!("reversing\nchanges made to {}.", head)));
+        assert!(!Path::new("b.txt").exists());
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "1\n2\nTHREE\n");
+    }
+}
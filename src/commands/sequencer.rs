@@ -0,0 +1,302 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::{
+        diff::display_path,
+        merge::{apply_tree_merge, merge_trees, FileMerger},
+        restore::reset_to_commit,
+        rev_list::RevQuery,
+        status::{changes_to_be_committed, changes_to_be_staged, index_blobs},
+    },
+    models::{head, Commit, Hash, Index},
+    utils::{revision, util},
+};
+
+/// 逐个应用commit的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Action {
+    /// cherry-pick：应用commit相对于parent的更改
+    #[default]
+    Pick,
+    /// revert：应用commit的反向更改
+    Revert,
+}
+
+impl Action {
+    fn name(&self) -> &'static str {
+        match self {
+            Action::Pick => "cherry-pick",
+            Action::Revert => "revert",
+        }
+    }
+
+    /// 冲突时记录当前commit的文件
+    fn head_file(&self) -> &'static str {
+        match self {
+            Action::Pick => "CHERRY_PICK_HEAD",
+            Action::Revert => "REVERT_HEAD",
+        }
+    }
+}
+
+/// cherry-pick / revert 的选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SequenceOptions {
+    /// -n：只应用更改，不提交
+    pub no_commit: bool,
+    /// -x：在提交信息末尾记录来源（仅cherry-pick）
+    pub record_origin: bool,
+    /// -m：merge commit以第几个parent(从1开始)为基准
+    pub mainline: Option<usize>,
+}
+
+/** 进行中的cherry-pick / revert序列，冲突时保存到 .mit/sequencer，当前commit另外写入 CHERRY_PICK_HEAD / REVERT_HEAD */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Sequence {
+    action: Action,
+    /// 待处理的commit，第一个为当前commit
+    todo: Vec<Hash>,
+    /// 开始前的HEAD，用于--abort
+    orig_head: Hash,
+    options: SequenceOptions,
+}
+
+fn state_file(name: &str) -> PathBuf {
+    util::get_storage_path().unwrap().join(name)
+}
+
+impl Sequence {
+    fn load() -> Option<Sequence> {
+        let json = fs::read_to_string(state_file("sequencer")).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// 加载指定操作的序列，不存在时打印错误
+    fn load_for(action: Action) -> Option<Sequence> {
+        let seq = Sequence::load().filter(|seq| seq.action == action);
+        if seq.is_none() {
+            println!("error: no {} in progress", action.name());
+        }
+        seq
+    }
+
+    fn save(&self) {
+        fs::write(state_file("sequencer"), serde_json::to_string_pretty(self).unwrap()).unwrap();
+        fs::write(state_file(self.action.head_file()), &self.todo[0]).unwrap();
+    }
+
+    /// 当前commit已处理完毕
+    fn advance(&mut self) {
+        self.todo.remove(0);
+        let _ = fs::remove_file(state_file(self.action.head_file()));
+    }
+
+    fn clear(&self) {
+        let _ = fs::remove_file(state_file("sequencer"));
+        let _ = fs::remove_file(state_file(self.action.head_file()));
+    }
+}
+
+/// 正在处理（有冲突）的commit，e.g. CHERRY_PICK_HEAD
+pub fn current(action: Action) -> Option<Hash> {
+    fs::read_to_string(state_file(action.head_file()))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// 简短描述，e.g. `a1b2c3d... fix typo`
+pub fn describe(commit: &Commit) -> String {
+    let hash = commit.get_hash();
+    let subject = commit.get_message().lines().next().unwrap_or_default().to_string();
+    format!("{}... {}", &hash[..7], subject)
+}
+
+/// 作为基准的parent，没有parent时为None
+fn base_parent(commit: &Commit, mainline: Option<usize>) -> Option<Hash> {
+    commit.get_parent_hash().get(mainline.unwrap_or(1) - 1).cloned()
+}
+
+fn tree_blobs(commit: Option<&Hash>) -> Vec<(PathBuf, Hash)> {
+    match commit {
+        Some(hash) => Commit::load(hash).get_tree().get_recursive_blobs(),
+        None => Vec::new(),
+    }
+}
+
+/** 将commit的更改三路合并到当前index，返回冲突文件
+<br>pick：base为parent，theirs为commit；revert则相反
+ */
+pub fn apply(action: Action, hash: &Hash, mainline: Option<usize>) -> Vec<PathBuf> {
+    let commit = Commit::load(hash);
+    let parent = base_parent(&commit, mainline);
+    let (base, theirs, label) = match action {
+        Action::Pick => (tree_blobs(parent.as_ref()), tree_blobs(Some(hash)), describe(&commit)),
+        Action::Revert => {
+            (tree_blobs(Some(hash)), tree_blobs(parent.as_ref()), format!("parent of {}", describe(&commit)))
+        }
+    };
+    // 以index为ours，-n时连续应用的更改可以累积
+    let ours = index_blobs(Index::get_instance());
+    let result = merge_trees(&base, &ours, &theirs, &FileMerger::new("HEAD", &label, None));
+    apply_tree_merge(&result);
+    result.conflicts.into_iter().map(|c| c.path).collect()
+}
+
+/// 提交当前index：pick保留原作者和提交信息，revert生成提交信息；没有任何更改时跳过
+fn commit_result(action: Action, hash: &Hash, options: &SequenceOptions) {
+    let original = Commit::load(hash);
+    if changes_to_be_committed().is_empty() {
+        println!("The previous {} is now empty, skipping {}", action.name(), describe(&original));
+        return;
+    }
+    let message = match action {
+        Action::Pick if options.record_origin => {
+            format!("{}\n\n(cherry picked from commit {})", original.get_message().trim_end(), hash)
+        }
+        Action::Pick => original.get_message(),
+        Action::Revert => {
+            let subject = original.get_message().lines().next().unwrap_or_default().to_string();
+            let mut message = format!("Revert \"{}\"\n\nThis reverts commit {}", subject, hash);
+            match base_parent(&original, options.mainline) {
+                Some(parent) if options.mainline.is_some() => {
+                    message.push_str(&format!(", reversing\nchanges made to {}.", parent))
+                }
+                _ => message.push('.'),
+            }
+            message
+        }
+    };
+    let head_commit = head::current_head_commit();
+    let parents = if head_commit.is_empty() {
+        vec![]
+    } else {
+        vec![head_commit]
+    };
+    let index = Index::get_instance();
+    let mut commit = Commit::new(index, parents, message);
+    if action == Action::Pick {
+        commit.set_author(original.get_author());
+    }
+    let commit_hash = commit.save();
+    let subject = commit.get_message().lines().next().unwrap_or_default().to_string();
+    head::update_head_commit(&commit_hash, &format!("{}: {}", action.name(), subject));
+    index.save();
+    println!("[{}] {}", &commit_hash[..7], describe(&commit));
+}
+
+/// 依次处理todo中的commit，遇到冲突时保存状态并停止
+fn run(mut seq: Sequence) {
+    while let Some(hash) = seq.todo.first().cloned() {
+        let conflicts = apply(seq.action, &hash, seq.options.mainline);
+        if !conflicts.is_empty() {
+            seq.save();
+            println!("error: could not {} {}", seq.action.name(), describe(&Commit::load(&hash)));
+            for path in &conflicts {
+                println!("\t{}", display_path(path));
+            }
+            println!("hint: after resolving the conflicts, mark the corrected paths with 'mit add <paths>'");
+            println!("hint: and run 'mit {0} --continue', or skip it with 'mit {0} --skip'", seq.action.name());
+            return;
+        }
+        if !seq.options.no_commit {
+            commit_result(seq.action, &hash, &seq.options);
+        }
+        seq.advance();
+    }
+    seq.clear();
+}
+
+/// 解析参数为待处理的commit：单个commit按顺序，范围(`A..B`)内pick从旧到新、revert从新到旧
+fn resolve_commits(action: Action, revs: &[String], mainline: Option<usize>) -> Result<Vec<Hash>, String> {
+    let mut todo = Vec::new();
+    for rev in revs {
+        if rev.contains("..") && !rev.starts_with(":/") {
+            let query = RevQuery::parse(&[rev]).map_err(|e| e.to_string())?;
+            let mut commits: Vec<Hash> = query.run().iter().map(|c| c.get_hash()).collect();
+            if action == Action::Pick {
+                commits.reverse();
+            }
+            todo.extend(commits);
+        } else {
+            todo.push(revision::resolve_commit(rev).map_err(|e| e.to_string())?);
+        }
+    }
+    for hash in &todo {
+        let parents = Commit::load(hash).get_parent_hash().len();
+        match mainline {
+            None if parents > 1 => {
+                return Err(format!("error: commit {} is a merge but no -m option was given.", hash))
+            }
+            Some(_) if parents <= 1 => {
+                return Err(format!("error: mainline was specified but commit {} is not a merge.", hash))
+            }
+            Some(m) if m == 0 || m > parents => {
+                return Err(format!("error: commit {} does not have parent {}", hash, m))
+            }
+            _ => {}
+        }
+    }
+    Ok(todo)
+}
+
+/// 开始新的序列
+pub fn start(action: Action, revs: Vec<String>, options: SequenceOptions) {
+    util::check_repo_exist();
+    if let Some(seq) = Sequence::load() {
+        println!("error: a {} is already in progress", seq.action.name());
+        println!("hint: try \"mit {} (--continue | --skip | --abort)\"", seq.action.name());
+        return;
+    }
+    if !changes_to_be_staged().is_empty() || !changes_to_be_committed().is_empty() {
+        println!("error: your local changes would be overwritten by {}.", action.name());
+        println!("hint: commit your changes or stash them to proceed.");
+        return;
+    }
+    let todo = match resolve_commits(action, &revs, options.mainline) {
+        Ok(todo) => todo,
+        Err(err) => return println!("{}", err),
+    };
+    let orig_head = head::current_head_commit();
+    run(Sequence { action, todo, orig_head, options });
+}
+
+/// 冲突解决（并add）后继续
+pub fn resume(action: Action) {
+    let Some(mut seq) = Sequence::load_for(action) else {
+        return;
+    };
+    if !Index::get_instance().get_conflicts().is_empty() {
+        println!("error: Committing is not possible because you have unmerged files.");
+        return;
+    }
+    if !seq.options.no_commit {
+        // 用户已经手动commit时，这里没有更改，会直接跳过
+        commit_result(action, &seq.todo[0], &seq.options);
+    }
+    seq.advance();
+    run(seq);
+}
+
+/// 丢弃当前commit的更改，继续处理后面的commit
+pub fn skip(action: Action) {
+    let Some(mut seq) = Sequence::load_for(action) else {
+        return;
+    };
+    reset_to_commit(&head::current_head_commit());
+    seq.advance();
+    run(seq);
+}
+
+/// 放弃整个序列，恢复到开始前的状态
+pub fn abort(action: Action) {
+    let Some(seq) = Sequence::load_for(action) else {
+        return;
+    };
+    if !seq.orig_head.is_empty() {
+        head::update_head_commit(&seq.orig_head, &format!("{}: abort", seq.action.name()));
+    }
+    reset_to_commit(&seq.orig_head);
+    seq.clear();
+}
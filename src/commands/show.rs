@@ -0,0 +1,226 @@
+use std::path::PathBuf;
+
+use colored::Colorize;
+
+use crate::{
+    commands::{
+        diff::{display_path, file_diff_lines},
+        status,
+    },
+    models::{Blob, Commit, Hash, Index, Tag, Tree},
+    utils::{
+        diff::DiffOptions,
+        path_ext::PathExt,
+        revision,
+        util::{self, ObjectType},
+    },
+};
+
+fn blob_content(hash: &Hash) -> String {
+    Blob::load(hash).get_content()
+}
+
+/// commit头部信息、提交信息和相对于第一个parent的diff
+fn show_commit(commit: &Commit, options: &DiffOptions) -> Vec<String> {
+    let mut lines = vec![format!("commit {}", commit.get_hash()).yellow().to_string()];
+    let parents = commit.get_parent_hash();
+    if parents.len() > 1 {
+        let short: Vec<&str> = parents.iter().map(|p| &p[..7.min(p.len())]).collect();
+        lines.push(format!("Merge: {}", short.join(" ")));
+    }
+    lines.push(format!("Author: {}", commit.get_author()));
+    lines.push(format!("Date:   {}", commit.get_date()));
+    lines.push(String::new());
+    lines.extend(commit.get_message().lines().map(|line| format!("    {}", line)));
+    lines.push(String::new());
+
+    let blobs = commit.get_tree().get_recursive_blobs();
+    let parent_blobs = match parents.first() {
+        Some(parent) => Commit::load(parent).get_tree().get_recursive_blobs(),
+        None => Vec::new(),
+    };
+    let find =
+        |blobs: &[(PathBuf, Hash)], path: &PathBuf| blobs.iter().find(|(p, _)| p == path).map(|(_, h)| blob_content(h));
+    let changes = status::compare_blobs(&parent_blobs, &blobs, &status::RenameOptions::from_config());
+    for (renames, kind) in [(&changes.renamed, "rename"), (&changes.copied, "copy")] {
+        for r in renames {
+            let (from, to) = (display_path(&r.from), display_path(&r.to));
+            let headers = [
+                format!("similarity index {}%", r.score),
+                format!("{} from {}", kind, from),
+                format!("{} to {}", kind, to),
+            ];
+            let old = find(&parent_blobs, &r.from);
+            let new = find(&blobs, &r.to);
+            lines.extend(file_diff_lines(&from, &to, old.as_deref(), new.as_deref(), options, &headers));
+        }
+    }
+    let mut files: Vec<&PathBuf> = changes.new.iter().chain(&changes.modified).chain(&changes.deleted).collect();
+    files.sort();
+    for file in files {
+        let old = find(&parent_blobs, file);
+        let new = find(&blobs, file);
+        let path = display_path(file);
+        lines.extend(file_diff_lines(&path, &path, old.as_deref(), new.as_deref(), options, &[]));
+    }
+    lines
+}
+
+/// tree的直接条目，目录以'/'结尾
+fn show_tree(tree: &Tree) -> Vec<String> {
+    let mut entries = tree.entries.clone();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+        .into_iter()
+        .map(|entry| {
+            if entry.filemode.0 == "tree" {
+                format!("{}/", entry.name)
+            } else {
+                entry.name
+            }
+        })
+        .collect()
+}
+
+/// 文件内容的各行
+fn show_blob(hash: &Hash) -> Vec<String> {
+    blob_content(hash).lines().map(String::from).collect()
+}
+
+/// 将`<rev>:<path>`中的path转换为相对workdir的路径：默认相对于根目录；以./或../开头时相对于当前目录
+fn to_tree_path(path: &str) -> PathBuf {
+    if path.starts_with("./") || path.starts_with("../") {
+        util::to_workdir_relative_path(&util::get_absolute_path(path.as_ref()))
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+/// `:<path>`，即暂存区中的文件内容
+fn show_index_path(path: &str) -> Result<Vec<String>, String> {
+    let path = to_tree_path(path);
+    let hash = Index::get_instance()
+        .get_hash(&path.to_absolute_workdir())
+        .ok_or(format!("fatal: path '{}' does not exist in the index", display_path(&path)))?;
+    Ok(show_blob(&hash))
+}
+
+/// `<rev>:<path>` 对应的对象：文件内容或目录列表
+fn show_path(rev: &str, path: &str) -> Result<Vec<String>, String> {
+    let tree_hash = revision::resolve(&format!("{}^{{tree}}", rev)).map_err(|e| e.to_string())?;
+    let tree = Tree::load(&tree_hash);
+    let path = to_tree_path(path);
+    if path.as_os_str().is_empty() {
+        return Ok(show_tree(&tree));
+    }
+    let entry =
+        tree.find_entry(&path)
+            .ok_or(format!("fatal: path '{}' does not exist in '{}'", display_path(&path), rev))?;
+    if entry.filemode.0 == "tree" {
+        let mut lines = vec![format!("tree {}:{}", rev, display_path(&path)).yellow().to_string(), String::new()];
+        lines.extend(show_tree(&Tree::load(&entry.object_hash)));
+        Ok(lines)
+    } else {
+        Ok(show_blob(&entry.object_hash))
+    }
+}
+
+/// 一个对象的输出，每个元素为一行
+fn show_object(rev: &str, options: &DiffOptions) -> Result<Vec<String>, String> {
+    if !rev.starts_with(":/") {
+        // `:/regex` 是提交信息搜索，不是暂存区中的路径
+        if let Some((rev, path)) = rev.split_once(':') {
+            return if rev.is_empty() {
+                show_index_path(path)
+            } else {
+                show_path(rev, path)
+            };
+        }
+    }
+    let hash = revision::resolve(rev).map_err(|e| e.to_string())?;
+    let lines = match util::check_object_type(hash.clone()) {
+        ObjectType::Commit => show_commit(&Commit::load(&hash), options),
+        ObjectType::Tree => {
+            let mut lines = vec![format!("tree {}", hash).yellow().to_string(), String::new()];
+            lines.extend(show_tree(&Tree::load(&hash)));
+            lines
+        }
+        ObjectType::Blob => show_blob(&hash),
+        ObjectType::Tag => {
+            let tag = Tag::load(&hash);
+            let mut lines = vec![
+                format!("tag {}", tag.get_name()).yellow().to_string(),
+                format!("Tagger: {}", tag.get_tagger()),
+                format!("Date:   {}", tag.get_date()),
+                String::new(),
+                tag.get_message(),
+                String::new(),
+            ];
+            lines.extend(show_object(&tag.get_object(), options)?);
+            lines
+        }
+        ObjectType::Invalid => return Err(format!("fatal: invalid object name '{}'", rev)),
+    };
+    Ok(lines)
+}
+
+/** 显示对象：commit显示头部、提交信息和diff；tree列出条目；blob打印内容
+<br>`<rev>:<path>` 显示某个版本中的文件内容
+ */
+pub fn show(revs: Vec<String>, options: DiffOptions) {
+    util::check_repo_exist();
+    let revs = if revs.is_empty() {
+        vec!["HEAD".to_string()]
+    } else {
+        revs
+    };
+    for rev in revs {
+        match show_object(&rev, &options) {
+            Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+            Err(err) => println!("{}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{commands as cmd, models::head, utils::test};
+    use std::path::Path;
+
+    #[test]
+    fn test_show() {
+        test::setup_with_empty_workdir();
+        colored::control::set_override(false);
+        let show = |rev: &str| show_object(rev, &DiffOptions::default());
+        assert!(show("HEAD").is_err());
+
+        test::ensure_file(Path::new("a.txt"), Some("one\ntwo\n"));
+        test::ensure_file(Path::new("dir/b.txt"), Some("b"));
+        cmd::add(vec![], true, false);
+        cmd::commit("init".to_string(), false);
+        test::ensure_file(Path::new("a.txt"), Some("one\n2\n"));
+        cmd::add(vec![], true, false);
+        cmd::commit("second".to_string(), false);
+
+        let commit = Commit::load(&head::current_head_commit());
+        let lines = show("HEAD").unwrap();
+        assert_eq!(lines[0], format!("commit {}", commit.get_hash()));
+        assert_eq!(lines[4], "    second");
+        assert_eq!(
+            lines[6..],
+            ["diff --mit a/a.txt b/a.txt", "--- a/a.txt", "+++ b/a.txt", "@@ -1,2 +1,2 @@", " one", "-two", "+2"]
+        );
+        assert_eq!(show(&commit.get_hash()[..7]).unwrap(), lines);
+        assert_eq!(show(&commit.get_tree().get_hash()).unwrap()[2..], ["a.txt", "dir/"]);
+        assert_eq!(show("HEAD:a.txt").unwrap(), ["one", "2"]);
+        assert_eq!(show("HEAD~1:a.txt").unwrap(), ["one", "two"]);
+        assert_eq!(show("HEAD:dir").unwrap(), ["tree HEAD:dir", "", "b.txt"]);
+        assert_eq!(show(":dir/b.txt").unwrap(), ["b"]);
+        assert!(show(":missing.txt").is_err());
+        assert!(show("HEAD:missing.txt").is_err());
+        assert!(show("no-such-rev").is_err());
+        assert_eq!(show(":/init").unwrap()[0], format!("commit {}", commit.get_parent_hash()[0]));
+        assert_eq!(show(":/sec").unwrap()[0], format!("commit {}", commit.get_hash()));
+    }
+}
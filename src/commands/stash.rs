@@ -0,0 +1,321 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    commands::{
+        diff::display_path,
+        log::print_name_status,
+        merge::{apply_tree_merge, merge_trees, FileMerger},
+        restore::{reset_to_commit, restore_index},
+        status::{changes_to_be_committed, changes_to_be_staged, index_blobs},
+    },
+    models::{head, reflog, reflog::ReflogEntry, Blob, Commit, FileMetaData, Hash, Index},
+    utils::{path_ext::PathExt, util},
+};
+
+fn stash_ref() -> PathBuf {
+    util::get_storage_path().unwrap().join("refs").join("stash")
+}
+
+/// 读取stash列表（logs/refs/stash），按从新到旧排序，即 `stash@{0}` 为最新的
+fn read_entries() -> Vec<ReflogEntry> {
+    reflog::read("refs/stash")
+}
+
+/// 重写stash列表，并将 refs/stash 指向最新的一项；列表为空时删除
+fn write_entries(entries: &[ReflogEntry]) {
+    if entries.is_empty() {
+        let _ = fs::remove_file(stash_ref());
+        reflog::delete("refs/stash");
+        return;
+    }
+    // 删除中间项后，重新串联old
+    let mut entries = entries.to_vec();
+    for i in 0..entries.len() {
+        entries[i].old = entries.get(i + 1).map(|next| next.new.clone()).unwrap_or("0".repeat(40));
+    }
+    reflog::write("refs/stash", &entries);
+    fs::write(stash_ref(), &entries[0].new).unwrap();
+}
+
+/// 解析 `stash@{n}` 或 `n`，默认为最新的stash
+fn find_entry(stash: Option<String>) -> Option<(usize, ReflogEntry)> {
+    let entries = read_entries();
+    if entries.is_empty() {
+        println!("No stash entries found.");
+        return None;
+    }
+    let stash = stash.unwrap_or("stash@{0}".to_string());
+    let n = stash
+        .strip_prefix("stash@{")
+        .and_then(|s| s.strip_suffix('}'))
+        .unwrap_or(&stash);
+    match n
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| entries.get(n).map(|entry| (n, entry.clone())))
+    {
+        Some(found) => Some(found),
+        None => {
+            println!("error: {} is not a valid reference", stash);
+            None
+        }
+    }
+}
+
+fn tree_blobs(commit: &Hash) -> Vec<(PathBuf, Hash)> {
+    Commit::load(commit).get_tree().get_recursive_blobs()
+}
+
+/** 保存当前的暂存区和工作区，然后恢复到HEAD
+<br>stash为一个特殊的commit：tree为工作区（已跟踪的文件），parent依次为HEAD、保存暂存区的commit，
+`-u`时还有保存未跟踪文件的commit
+ */
+pub fn push(message: Option<String>, include_untracked: bool) {
+    util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
+    let head_commit = head::current_head_commit();
+    if head_commit.is_empty() {
+        return println!("You do not have the initial commit yet");
+    }
+    let index = Index::get_instance();
+    if !index.get_conflicts().is_empty() {
+        return println!("error: could not save the current state: you have unmerged files.");
+    }
+    let staged = changes_to_be_committed();
+    let unstaged = changes_to_be_staged();
+    let untracked = if include_untracked {
+        unstaged.new.clone()
+    } else {
+        Vec::new()
+    };
+    if staged.is_empty() && unstaged.modified.is_empty() && unstaged.deleted.is_empty() && untracked.is_empty() {
+        return println!("No local changes to save");
+    }
+
+    let branch = match head::current_head() {
+        head::Head::Branch(name) => name,
+        head::Head::Detached(_) => "(no branch)".to_string(),
+    };
+    let subject = Commit::load(&head_commit)
+        .get_message()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let on = format!("{}: {} {}", branch, &head_commit[..7], subject);
+
+    let index_commit = Commit::new(index, vec![head_commit.clone()], format!("index on {}", on)).save();
+    let mut parents = vec![head_commit.clone(), index_commit];
+    if !untracked.is_empty() {
+        let mut files = Index::default();
+        for path in &untracked {
+            let path = path.to_absolute_workdir();
+            let blob = Blob::new(util::read_workfile(&path));
+            files.add(path.clone(), FileMetaData::new(&blob, &path));
+        }
+        parents.push(Commit::new(&files, vec![], format!("untracked files on {}", on)).save());
+    }
+    // 工作区中已跟踪的文件
+    let mut worktree = Index::default();
+    for (path, data) in index.get_tracked_entries() {
+        if !path.exists() {
+            continue;
+        }
+        if index.is_modified(&path) {
+            let blob = Blob::new(util::read_workfile(&path));
+            worktree.add(path.clone(), FileMetaData::new(&blob, &path));
+        } else {
+            worktree.add(path, data);
+        }
+    }
+    let message = match message {
+        Some(message) => format!("On {}: {}", branch, message),
+        None => format!("WIP on {}", on),
+    };
+    let stash = Commit::new(&worktree, parents, message.clone()).save();
+
+    let mut entries = read_entries();
+    entries.insert(0, ReflogEntry::new("", &stash, &message));
+    write_entries(&entries);
+
+    reset_to_commit(&head_commit);
+    for path in &untracked {
+        let path = path.to_absolute_workdir();
+        fs::remove_file(&path).unwrap();
+        util::clear_empty_dir(&path);
+    }
+    println!("Saved working directory and index state {}", message);
+}
+
+/** 将stash中的更改合并到当前的工作区和暂存区，返回是否成功（无冲突）
+<br>工作区有未暂存的更改，或未跟踪的文件已存在时拒绝
+ */
+fn apply_entry(entry: &ReflogEntry) -> bool {
+    let stash = Commit::load(&entry.new);
+    let parents = stash.get_parent_hash();
+    let unstaged = changes_to_be_staged();
+    if !unstaged.modified.is_empty() || !unstaged.deleted.is_empty() {
+        println!("error: Your local changes to the following files would be overwritten by merge:");
+        for path in unstaged.modified.iter().chain(&unstaged.deleted) {
+            println!("\t{}", display_path(path));
+        }
+        println!("Please commit your changes or stash them before you merge.");
+        return false;
+    }
+    let untracked = parents.get(2).map(tree_blobs).unwrap_or_default();
+    let existing: Vec<&PathBuf> = untracked
+        .iter()
+        .map(|(path, _)| path)
+        .filter(|path| path.to_absolute_workdir().exists())
+        .collect();
+    if !existing.is_empty() {
+        for path in existing {
+            println!("{} already exists, no checkout", display_path(path));
+        }
+        println!("error: could not restore untracked files from stash");
+        return false;
+    }
+
+    let base = tree_blobs(&parents[0]);
+    let orig_index = index_blobs(Index::get_instance());
+    let merger = FileMerger::new("Updated upstream", "Stashed changes", None);
+    let result = merge_trees(&base, &orig_index, &stash.get_tree().get_recursive_blobs(), &merger);
+    apply_tree_merge(&result);
+    for (path, hash) in &untracked {
+        util::write_workfile(Blob::load(hash).get_content(), &path.to_absolute_workdir());
+    }
+    if !result.conflicts.is_empty() {
+        for conflict in &result.conflicts {
+            println!("CONFLICT (content): Merge conflict in {}", display_path(&conflict.path));
+        }
+        return false;
+    }
+    // 恢复暂存区：只有stash时暂存的更改重新暂存
+    let staged = merge_trees(&base, &orig_index, &tree_blobs(&parents[1]), &merger);
+    if staged.conflicts.is_empty() {
+        restore_index(None, &staged.blobs);
+    }
+    true
+}
+
+/// 应用stash，保留在列表中
+pub fn apply(stash: Option<String>) {
+    util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
+    if let Some((_, entry)) = find_entry(stash) {
+        apply_entry(&entry);
+    }
+}
+
+/// 应用stash并从列表中删除；有冲突时保留
+pub fn pop(stash: Option<String>) {
+    util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
+    let Some((n, entry)) = find_entry(stash) else {
+        return;
+    };
+    if apply_entry(&entry) {
+        drop_entry(n);
+    } else {
+        println!("The stash entry is kept in case you need it again.");
+    }
+}
+
+fn drop_entry(n: usize) {
+    let mut entries = read_entries();
+    let entry = entries.remove(n);
+    write_entries(&entries);
+    println!("Dropped stash@{{{}}} ({})", n, entry.new);
+}
+
+/// 从列表中删除stash
+pub fn drop(stash: Option<String>) {
+    util::check_repo_exist();
+    if let Some((n, _)) = find_entry(stash) {
+        drop_entry(n);
+    }
+}
+
+/// 列出所有stash，最新的在前
+pub fn list() {
+    util::check_repo_exist();
+    for (i, entry) in read_entries().iter().enumerate() {
+        println!("stash@{{{}}}: {}", i, entry.message);
+    }
+}
+
+/// 显示stash中工作区相对于HEAD的文件变更
+pub fn show(stash: Option<String>) {
+    util::check_repo_exist();
+    if let Some((_, entry)) = find_entry(stash) {
+        print_name_status(&Commit::load(&entry.new));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{commands as cmd, utils::test};
+    use std::path::Path;
+
+    #[test]
+    fn test_stash() {
+        test::setup_with_empty_workdir();
+        test::ensure_file(Path::new("a.txt"), Some("a\n"));
+        test::ensure_file(Path::new("b.txt"), Some("b\n"));
+        cmd::add(vec![], true, false);
+        cmd::commit("base".to_string(), false);
+        let base = head::current_head_commit();
+
+        push(None, false);
+        assert!(read_entries().is_empty());
+
+        // 暂存的修改 + 未暂存的修改 + 未跟踪的文件
+        test::ensure_file(Path::new("a.txt"), Some("staged\n"));
+        cmd::add(vec!["a.txt".to_string()], false, false);
+        test::ensure_file(Path::new("b.txt"), Some("unstaged\n"));
+        test::ensure_file(Path::new("dir/c.txt"), Some("untracked\n"));
+        push(Some("work".to_string()), true);
+        assert_eq!(head::current_head_commit(), base);
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "a\n");
+        assert_eq!(fs::read_to_string("b.txt").unwrap(), "b\n");
+        assert!(!Path::new("dir/c.txt").exists());
+        assert!(changes_to_be_committed().is_empty());
+
+        // 第二个stash，只有未跟踪文件时不带-u则没有更改
+        test::ensure_file(Path::new("b.txt"), Some("second\n"));
+        push(None, false);
+        let entries = read_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].message, "On master: work");
+        assert!(entries[0].message.starts_with("WIP on master: "));
+        assert_eq!(fs::read_to_string(stash_ref()).unwrap(), entries[0].new);
+
+        // apply保留，drop删除
+        apply(None);
+        assert_eq!(fs::read_to_string("b.txt").unwrap(), "second\n");
+        assert!(changes_to_be_committed().is_empty());
+        reset_to_commit(&base);
+        drop(Some("stash@{0}".to_string()));
+        assert_eq!(read_entries().len(), 1);
+        assert_eq!(fs::read_to_string(stash_ref()).unwrap(), entries[1].new);
+
+        // pop：恢复暂存区、工作区和未跟踪文件
+        pop(None);
+        assert!(read_entries().is_empty());
+        assert!(!stash_ref().exists());
+        assert_eq!(fs::read_to_string("a.txt").unwrap(), "staged\n");
+        assert_eq!(fs::read_to_string("b.txt").unwrap(), "unstaged\n");
+        assert_eq!(fs::read_to_string("dir/c.txt").unwrap(), "untracked\n");
+        assert_eq!(changes_to_be_committed().modified, vec![PathBuf::from("a.txt")]);
+        let unstaged = changes_to_be_staged();
+        assert_eq!(unstaged.modified, vec![PathBuf::from("b.txt")]);
+        assert_eq!(unstaged.new, vec![PathBuf::from("dir/c.txt")]);
+    }
+}
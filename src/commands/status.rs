@@ -1,11 +1,23 @@
+use crate::commands::{branch, cherry_pick, merge, rebase, rev_list, revert};
 use crate::models::head;
 use crate::utils::path_ext::PathExt;
 use crate::{
-    models::{Blob, Commit, Index},
-    utils::util,
+    models::{Blob, Commit, ConflictStages, Hash, Index},
+    utils::{config::Config, diff, util},
 };
 use colored::Colorize;
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+/// 重命名或复制：from -> to，score为相似度(%)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub score: u32,
+}
 
 /** 获取需要commit的更改(staged)
    注：相对路径(to workdir)
@@ -15,11 +27,17 @@ pub struct Changes {
     pub new: Vec<PathBuf>,
     pub modified: Vec<PathBuf>,
     pub deleted: Vec<PathBuf>,
+    pub renamed: Vec<Rename>,
+    pub copied: Vec<Rename>,
 }
 
 impl Changes {
     pub fn is_empty(&self) -> bool {
-        self.new.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+        self.new.is_empty()
+            && self.modified.is_empty()
+            && self.deleted.is_empty()
+            && self.renamed.is_empty()
+            && self.copied.is_empty()
     }
 
     /// 使用paths过滤，返回绝对路径
@@ -29,6 +47,10 @@ impl Changes {
         change.new = util::filter_to_fit_paths(&abs_self.new, paths);
         change.modified = util::filter_to_fit_paths(&abs_self.modified, paths);
         change.deleted = util::filter_to_fit_paths(&abs_self.deleted, paths);
+        // 重命名只要一端在paths中就保留
+        let fit = |r: &&Rename| r.from.include_in(paths) || r.to.include_in(paths);
+        change.renamed = abs_self.renamed.iter().filter(fit).cloned().collect();
+        change.copied = abs_self.copied.iter().filter(fit).cloned().collect();
         change
     }
 
@@ -38,31 +60,31 @@ impl Changes {
         self.filter_abs(paths).to_relative_from_abs()
     }
 
-    /// 转换为绝对路径（from workdir相对路径）
-    pub fn to_absolute(&self) -> Changes {
+    /// 对所有路径应用func
+    fn map_paths<F: Fn(&PathBuf) -> PathBuf>(&self, func: F) -> Changes {
         let mut change = self.clone();
-        // change.new = util::map(&self.new, |p| util::to_workdir_absolute_path(p));
-        // change.modified = util::map(&self.modified, |p| util::to_workdir_absolute_path(p));
-        // change.deleted = util::map(&self.deleted, |p| util::to_workdir_absolute_path(p));
         //离谱子
         [&mut change.new, &mut change.modified, &mut change.deleted]
             .iter_mut()
             .for_each(|paths| {
-                **paths = util::map(&**paths, |p| p.to_absolute_workdir());
+                **paths = util::map(&**paths, &func);
             });
+        change.renamed.iter_mut().chain(change.copied.iter_mut()).for_each(|r| {
+            r.from = func(&r.from);
+            r.to = func(&r.to);
+        });
         change
     }
 
+    /// 转换为绝对路径（from workdir相对路径）
+    pub fn to_absolute(&self) -> Changes {
+        self.map_paths(|p| p.to_absolute_workdir())
+    }
+
     /// 转换为相对路径（to cur_dir）注意：要先转换为绝对路径
     fn to_relative_from_abs(&self) -> Changes {
-        let mut change = self.clone();
         let cur_dir = util::cur_dir();
-        [&mut change.new, &mut change.modified, &mut change.deleted]
-            .iter_mut()
-            .for_each(|paths| {
-                **paths = util::map(&**paths, |p| util::get_relative_path_to_dir(p, &cur_dir));
-            });
-        change
+        self.map_paths(|p| util::get_relative_path_to_dir(p, &cur_dir))
     }
 
     ///转换为相对路径（to cur_dir）
@@ -71,47 +93,176 @@ impl Changes {
     }
 }
 
+/// 重命名 & 复制检测的选项
+#[derive(Debug, Clone, Copy)]
+pub struct RenameOptions {
+    /// 是否检测重命名
+    pub renames: bool,
+    /// 是否检测复制（来源为旧版本中的任意文件）
+    pub copies: bool,
+    /// 相似度阈值(%)，不低于该值才视为重命名/复制
+    pub threshold: u32,
+}
+
+/// 默认相似度阈值，同git
+pub const DEFAULT_RENAME_THRESHOLD: u32 = 50;
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        RenameOptions {
+            renames: true,
+            copies: false,
+            threshold: DEFAULT_RENAME_THRESHOLD,
+        }
+    }
+}
+
+impl RenameOptions {
+    /** 读取配置
+    <br>`diff.renames`: true | false | copies
+    <br>`diff.renameThreshold`: 0~100 */
+    pub fn from_config() -> RenameOptions {
+        let config = Config::load();
+        let mut options = RenameOptions::default();
+        if let Some(renames) = config.get("diff.renames") {
+            match renames.to_lowercase().as_str() {
+                "copies" | "copy" => options.copies = true,
+                "false" | "no" | "off" | "0" => options.renames = false,
+                _ => {}
+            }
+        }
+        if let Some(threshold) = config
+            .get("diff.renameThreshold")
+            .and_then(|t| t.trim_end_matches('%').parse().ok())
+        {
+            options.threshold = threshold;
+        }
+        options
+    }
+}
+
+/** 比较两组blob(相对路径)，得到 old -> new 的更改，并进行重命名 & 复制检测
+<br>用于HEAD vs index、commit vs parent 等tree之间的比较
+ */
+pub fn compare_blobs(old: &[(PathBuf, Hash)], new: &[(PathBuf, Hash)], options: &RenameOptions) -> Changes {
+    let mut change = Changes::default();
+    let old_map: HashMap<&PathBuf, &Hash> = old.iter().map(|(p, h)| (p, h)).collect();
+    let new_map: HashMap<&PathBuf, &Hash> = new.iter().map(|(p, h)| (p, h)).collect();
+    for (path, hash) in old {
+        match new_map.get(path) {
+            Some(new_hash) if *new_hash != hash => change.modified.push(path.clone()),
+            Some(_) => {}
+            None => change.deleted.push(path.clone()),
+        }
+    }
+    for (path, _) in new {
+        if !old_map.contains_key(path) {
+            change.new.push(path.clone());
+        }
+    }
+    if options.renames || options.copies {
+        detect_renames(&mut change, &old_map, &new_map, options);
+    }
+    change
+}
+
+/// 在deleted & new之间按相似度配对为重命名；剩余的new在旧版本所有文件中寻找复制来源
+fn detect_renames(
+    change: &mut Changes,
+    old: &HashMap<&PathBuf, &Hash>,
+    new: &HashMap<&PathBuf, &Hash>,
+    options: &RenameOptions,
+) {
+    if change.new.is_empty() {
+        return;
+    }
+    let mut contents: HashMap<Hash, String> = HashMap::new();
+    let mut load = |hash: &Hash| {
+        contents
+            .entry(hash.clone())
+            .or_insert_with(|| Blob::load(hash).get_content())
+            .clone()
+    };
+    let mut score = |from: &Hash, to: &Hash| {
+        if from == to {
+            100 // 内容完全相同，不需要计算
+        } else {
+            diff::similarity(&load(from), &load(to))
+        }
+    };
+
+    if options.renames && !change.deleted.is_empty() {
+        let mut candidates: Vec<Rename> = Vec::new();
+        for from in &change.deleted {
+            for to in &change.new {
+                let score = score(old[from], new[to]);
+                if score >= options.threshold {
+                    candidates.push(Rename { from: from.clone(), to: to.clone(), score });
+                }
+            }
+        }
+        // 相似度高的优先，相同时按路径排序保证结果稳定
+        candidates.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| (&a.from, &a.to).cmp(&(&b.from, &b.to))));
+        let mut used_from = HashSet::new();
+        let mut used_to = HashSet::new();
+        for rename in candidates {
+            if used_from.contains(&rename.from) || used_to.contains(&rename.to) {
+                continue;
+            }
+            used_from.insert(rename.from.clone());
+            used_to.insert(rename.to.clone());
+            change.renamed.push(rename);
+        }
+        change.deleted.retain(|p| !used_from.contains(p));
+        change.new.retain(|p| !used_to.contains(p));
+    }
+
+    if options.copies {
+        let mut sources: Vec<&PathBuf> = old.keys().copied().collect();
+        sources.sort();
+        let mut copied_to = HashSet::new();
+        for to in &change.new {
+            let best = sources
+                .iter()
+                .map(|from| (score(old[*from], new[to]), *from))
+                .filter(|(score, _)| *score >= options.threshold)
+                .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(a.1)));
+            if let Some((score, from)) = best {
+                change.copied.push(Rename { from: from.clone(), to: to.clone(), score });
+                copied_to.insert(to.clone());
+            }
+        }
+        change.new.retain(|p| !copied_to.contains(p));
+    }
+}
+
+/// index中跟踪的文件及其hash（相对路径 to workdir）
+pub fn index_blobs(index: &Index) -> Vec<(PathBuf, Hash)> {
+    index
+        .get_tracked_entries()
+        .into_iter()
+        .map(|(path, meta)| (path.to_relative_workdir(), meta.hash))
+        .collect()
+}
+
 /** 比较暂存区与HEAD(最后一次Commit::Tree)的差异
    注：相对路径(to workdir)
 */
 pub fn changes_to_be_committed() -> Changes {
-    let mut change = Changes::default();
     let index = Index::get_instance();
     let head_hash = head::current_head_commit();
-    let tracked_files = index
-        .get_tracked_files()
-        .iter()
-        .map(|f| f.to_relative_workdir())
-        .collect::<Vec<PathBuf>>();
+    let index_files = index_blobs(index);
     if head_hash.is_empty() {
         // 初始提交
-        change.new = tracked_files;
-        return change;
+        return Changes {
+            new: index_files.into_iter().map(|(path, _)| path).collect(),
+            ..Default::default()
+        };
     }
 
     let commit = Commit::load(&head_hash);
-    let tree = commit.get_tree();
-    let tree_files = tree.get_recursive_blobs(); //相对路径
-    let index_files: Vec<PathBuf> = tracked_files;
-
-    for (tree_file, blob_hash) in tree_files.iter() {
-        let index_file = index_files.iter().find(|&f| f == tree_file);
-        if let Some(index_file) = index_file {
-            let index_path = index_file.to_absolute_workdir();
-            if !index.verify_hash(&index_path, blob_hash) {
-                change.modified.push(tree_file.clone());
-            }
-        } else {
-            change.deleted.push(tree_file.clone());
-        }
-    }
-    for index_file in index_files.iter() {
-        let tree_item = tree_files.iter().find(|f| f.0 == *index_file);
-        if tree_item.is_none() {
-            change.new.push(index_file.clone());
-        }
-    }
-    change
+    let tree_files = commit.get_tree().get_recursive_blobs(); //相对路径
+    compare_blobs(&tree_files, &index_files, &RenameOptions::from_config())
 }
 
 /// 比较工作区与暂存区的差异，返回相对路径(to workdir)，不筛选
@@ -143,21 +294,115 @@ pub fn changes_to_be_staged() -> Changes {
 1. unstaged: 暂存区与工作区比较
 2. staged to be committed: 暂存区与HEAD(最后一次Commit::Tree)比较，即上次的暂存区
  */
+/// 冲突类型的描述，同git
+fn conflict_kind(stages: &ConflictStages) -> &'static str {
+    match (stages.base.is_some(), stages.ours.is_some(), stages.theirs.is_some()) {
+        (true, true, true) => "both modified",
+        (false, true, true) => "both added",
+        (_, true, false) => "deleted by them",
+        (_, false, true) => "deleted by us",
+        (_, false, false) => "both deleted",
+    }
+}
+
+/// 当前分支相对上游分支的状态，e.g. "Your branch is ahead of 'main' by 1 commit."
+fn print_tracking(branch_name: &String) {
+    let Some(upstream) = branch::upstream(branch_name) else {
+        return;
+    };
+    let (local, remote) = (head::get_branch_head(branch_name), head::get_branch_head(&upstream));
+    if local.is_empty() {
+        return;
+    }
+    if remote.is_empty() {
+        println!("Your branch is based on '{}', but the upstream is gone.", upstream);
+        return;
+    }
+    let plural = |n: usize| if n == 1 { "commit" } else { "commits" };
+    match rev_list::ahead_behind(&local, &remote) {
+        (0, 0) => println!("Your branch is up to date with '{}'.", upstream),
+        (ahead, 0) => println!("Your branch is ahead of '{}' by {} {}.", upstream, ahead, plural(ahead)),
+        (0, behind) => {
+            println!(
+                "Your branch is behind '{}' by {} {}, and can be fast-forwarded.",
+                upstream,
+                behind,
+                plural(behind)
+            );
+            println!("  (use \"mit merge\" to update your local branch)");
+        }
+        (ahead, behind) => {
+            println!("Your branch and '{}' have diverged,", upstream);
+            println!("and have {} and {} different commits each, respectively.", ahead, behind);
+        }
+    }
+}
+
 pub fn status() {
     util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
     match head::current_head() {
         head::Head::Detached(commit) => {
             println!("HEAD detached at {}", &commit[0..7]);
         }
         head::Head::Branch(branch) => {
             println!("On branch {}", branch);
+            if head::get_branch_head(&branch).is_empty() {
+                println!("\nNo commits yet\n");
+            }
+            print_tracking(&branch);
+        }
+    }
+
+    // 未解决冲突的文件单独列出，不计入其他更改
+    let cur_dir = util::cur_dir();
+    let conflicts: Vec<(PathBuf, ConflictStages)> = Index::get_instance()
+        .get_conflicts()
+        .into_iter()
+        .map(|(path, stages)| (util::get_relative_path_to_dir(&path, &cur_dir), stages))
+        .collect();
+    let unmerged = |path: &PathBuf| conflicts.iter().any(|(p, _)| p == path);
+    if let Some(commit) = cherry_pick::cherry_pick_head() {
+        println!("You are currently cherry-picking commit {}.", commit.get(..7).unwrap_or(&commit));
+        println!("  (fix conflicts and run \"mit cherry-pick --continue\")");
+        println!("  (use \"mit cherry-pick --skip\" to skip this patch)");
+        println!("  (use \"mit cherry-pick --abort\" to cancel the cherry-pick operation)");
+        println!();
+    } else if let Some(commit) = revert::revert_head() {
+        println!("You are currently reverting commit {}.", commit.get(..7).unwrap_or(&commit));
+        println!("  (fix conflicts and run \"mit revert --continue\")");
+        println!("  (use \"mit revert --skip\" to skip this patch)");
+        println!("  (use \"mit revert --abort\" to cancel the revert operation)");
+        println!();
+    } else if rebase::in_progress() {
+        println!("You are currently rebasing.");
+        println!("  (fix conflicts and then run \"mit rebase --continue\")");
+        println!("  (use \"mit rebase --skip\" to skip this patch)");
+        println!("  (use \"mit rebase --abort\" to check out the original branch)");
+        println!();
+    } else if merge::merge_head().is_some() {
+        if conflicts.is_empty() {
+            println!("All conflicts fixed but you are still merging.");
+            println!("  (use \"mit commit\" to conclude merge)");
+        } else {
+            println!("You have unmerged paths.");
+            println!("  (fix conflicts and run \"mit commit\")");
+            println!("  (use \"mit merge --abort\" to abort the merge)");
         }
+        println!();
     }
 
     // 对当前目录进行过滤 & 转换为相对路径
-    let staged = changes_to_be_committed().to_relative();
-    let unstaged = changes_to_be_staged().to_relative();
-    if staged.is_empty() && unstaged.is_empty() {
+    let mut staged = changes_to_be_committed().to_relative();
+    let mut unstaged = changes_to_be_staged().to_relative();
+    for paths in
+        [&mut staged.new, &mut staged.modified, &mut staged.deleted, &mut unstaged.modified, &mut unstaged.deleted]
+    {
+        paths.retain(|p| !unmerged(p));
+    }
+    if staged.is_empty() && unstaged.is_empty() && conflicts.is_empty() {
         println!("nothing to commit, working tree clean");
         return;
     }
@@ -169,6 +414,14 @@ pub fn status() {
             let str = format!("\tdeleted: {}", f.display());
             println!("{}", str.bright_green());
         });
+        staged.renamed.iter().for_each(|r| {
+            let str = format!("\trenamed: {} -> {}", r.from.display(), r.to.display());
+            println!("{}", str.bright_green());
+        });
+        staged.copied.iter().for_each(|r| {
+            let str = format!("\tcopied: {} -> {}", r.from.display(), r.to.display());
+            println!("{}", str.bright_green());
+        });
         staged.modified.iter().for_each(|f| {
             let str = format!("\tmodified: {}", f.display());
             println!("{}", str.bright_green());
@@ -179,6 +432,15 @@ pub fn status() {
         });
     }
 
+    if !conflicts.is_empty() {
+        println!("Unmerged paths:");
+        println!("  use \"mit add <file>...\" to mark resolution");
+        for (path, stages) in &conflicts {
+            let str = format!("\t{}: {}", conflict_kind(stages), path.display());
+            println!("{}", str.bright_red());
+        }
+    }
+
     if !unstaged.deleted.is_empty() || !unstaged.modified.is_empty() {
         println!("Changes not staged for commit:");
         println!("  use \"mit add <file>...\" to update what will be committed");
@@ -205,7 +467,7 @@ pub fn status() {
 mod tests {
     use super::*;
     use crate::{commands as cmd, utils::test};
-    use std::path::Path;
+    use std::{fs, path::Path};
 
     #[test]
     fn test_changes_to_be_committed() {
@@ -241,4 +503,45 @@ mod tests {
 
         println!("{:?}", change);
     }
+
+    #[test]
+    fn test_rename_and_copy_detection() {
+        test::setup_with_empty_workdir();
+        let content = "line 1\nline 2\nline 3\nline 4\n";
+        test::ensure_file(Path::new("a.txt"), Some(content));
+        test::ensure_file(Path::new("other.txt"), Some("other"));
+        cmd::add(vec![], true, false);
+        cmd::commit("init".to_string(), false);
+
+        // 移动并少量修改
+        fs::remove_file("a.txt").unwrap();
+        test::ensure_file(Path::new("dir/b.txt"), Some("line 1\nline 2\nline 3\nline 4 changed\n"));
+        cmd::add(vec![], true, false);
+        let change = changes_to_be_committed();
+        assert!(change.new.is_empty() && change.deleted.is_empty());
+        assert_eq!(change.renamed.len(), 1);
+        assert_eq!(change.renamed[0].from, PathBuf::from("a.txt"));
+        assert_eq!(change.renamed[0].to, PathBuf::from("dir/b.txt"));
+        assert_eq!(change.renamed[0].score, 75);
+
+        // 阈值高于相似度时不视为重命名
+        let mut config = Config::load();
+        config.set("diff.renameThreshold", "80");
+        config.save();
+        let change = changes_to_be_committed();
+        assert!(change.renamed.is_empty());
+        assert_eq!((change.new.len(), change.deleted.len()), (1, 1));
+
+        // 复制检测
+        cmd::commit("move".to_string(), false);
+        test::ensure_file(Path::new("copy.txt"), Some("other"));
+        cmd::add(vec![], true, false);
+        assert_eq!(changes_to_be_committed().new.len(), 1);
+        config.set("diff.renames", "copies");
+        config.save();
+        let change = changes_to_be_committed();
+        assert!(change.new.is_empty());
+        assert_eq!(change.copied[0].from, PathBuf::from("other.txt"));
+        status();
+    }
 }
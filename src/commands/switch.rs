@@ -2,7 +2,7 @@ use colored::Colorize;
 
 use crate::{
     models::{head, Commit, Hash},
-    utils::{store, util},
+    utils::{revision, util},
 };
 
 use super::{
@@ -29,8 +29,16 @@ fn switch_to_commit(commit_hash: Hash) {
     restore_index(None, &target_files);
 }
 
-fn switch_to(branch: String, detach: bool) -> Result<(), SwitchErr> {
-    // 检查更改
+/// 当前HEAD的描述：分支名或commit hash，用于记录切换历史
+fn describe_head() -> String {
+    match head::current_head() {
+        head::Head::Branch(branch) => branch,
+        head::Head::Detached(commit) => commit,
+    }
+}
+
+/// 检查更改，有未暂存或未提交的更改时不能切换
+fn check_clean() -> Result<(), SwitchErr> {
     let unstaged = status::changes_to_be_staged(); // unstaged.new是未跟踪 不需要处理
     if !unstaged.deleted.is_empty() || !unstaged.modified.is_empty() {
         status::status();
@@ -41,25 +49,45 @@ fn switch_to(branch: String, detach: bool) -> Result<(), SwitchErr> {
         println!("fatal: 你有未提交的更改，无法切换分支");
         return Err(SwitchErr::NoClean);
     }
+    Ok(())
+}
 
-    let store = store::Store::new();
+fn switch_to(branch: String, detach: bool) -> Result<(), SwitchErr> {
+    // `-` 等价于 `@{-1}`，即上一个分支
+    let branch = if branch == "-" { "@{-1}".to_string() } else { branch };
+    let branch = if branch.starts_with("@{-") {
+        match revision::resolve_previous_branch(&branch) {
+            Some(previous) => previous,
+            None => {
+                println!("fatal: 没有足够的切换历史: '{}'", branch);
+                return Err(SwitchErr::InvalidBranch);
+            }
+        }
+    } else {
+        branch
+    };
+
+    check_clean()?;
+
+    let from = describe_head();
     if head::list_local_branches().contains(&branch) {
         // 切到分支
         let branch_commit = head::get_branch_head(&branch);
         switch_to_commit(branch_commit.clone());
-        head::change_head_to_branch(&branch); // 更改head
+        head::change_head_to_branch(&branch, &format!("checkout: moving from {} to {}", from, branch)); // 更改head
         println!("切换到分支： '{}'", branch.green())
     } else if detach {
-        let commit = store.search(&branch);
-        if commit.is_none() || util::check_object_type(commit.clone().unwrap()) != util::ObjectType::Commit {
-            println!("fatal: 非法的 commit: '{}'", branch);
-            return Err(SwitchErr::InvalidObject);
-        }
+        let commit = match revision::resolve_commit(&branch) {
+            Ok(commit) => commit,
+            Err(err) => {
+                println!("{}", err);
+                return Err(SwitchErr::InvalidObject);
+            }
+        };
 
         // 切到commit
-        let commit = commit.unwrap();
         switch_to_commit(commit.clone());
-        head::change_head_to_commit(&commit); // 更改head
+        head::change_head_to_commit(&commit, &format!("checkout: moving from {} to {}", from, commit)); // 更改head
         println!("切换到 detach commit： '{}'", commit.yellow())
     } else {
         println!("fatal: 不存在分支 '{}'", branch);
@@ -69,12 +97,40 @@ fn switch_to(branch: String, detach: bool) -> Result<(), SwitchErr> {
     Ok(())
 }
 
+/** 切换到新的未出生(unborn)分支：清空暂存区，删除已跟踪的文件（未跟踪的文件保留）
+<br>分支在第一次commit时才会创建，该commit没有parent，即 `switch --orphan`
+ */
+fn orphan_to(branch: String) -> Result<(), SwitchErr> {
+    if !branch::check_new_branch_name(&branch) {
+        return Err(SwitchErr::InvalidBranch);
+    }
+    check_clean()?;
+
+    let from = describe_head();
+    restore_worktree(None, &Vec::new());
+    restore_index(None, &Vec::new());
+    head::change_head_to_branch(&branch, &format!("checkout: moving from {} to {}", from, branch));
+    println!("切换到新的孤立分支： '{}'", branch.green());
+    Ok(())
+}
+
+pub fn switch_orphan(new_branch: String) {
+    util::check_repo_exist();
+    if !util::check_worktree_exist() {
+        return;
+    }
+    let _ = orphan_to(new_branch);
+}
+
 pub fn switch(target_branch: Option<String>, create: Option<String>, detach: bool) {
+    if !util::check_worktree_exist() {
+        return;
+    }
     match create {
         Some(new_branch) => {
             // 以target_branch为基础创建新分支create
             println!("create new branch: {:?}", new_branch);
-            branch::branch(Some(new_branch.clone()), target_branch.clone(), false, None, false);
+            branch::branch(Some(new_branch.clone()), target_branch.clone(), Default::default());
             let _ = switch_to(new_branch, true);
         }
         None => {
@@ -91,14 +147,14 @@ mod test {
         commands::{self as cmd},
         utils::test,
     };
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     #[test]
     fn test_switch() {
         test::setup_with_empty_workdir();
 
         cmd::commit("init".to_string(), true);
         let test_branch_1 = "test_branch_1".to_string();
-        cmd::branch(Some(test_branch_1.clone()), None, false, None, false);
+        cmd::branch(Some(test_branch_1.clone()), None, Default::default());
 
         /* test 1: NoClean */
         let test_file_1 = PathBuf::from("test_file_1");
@@ -110,7 +166,7 @@ mod test {
 
         cmd::commit("add file 1".to_string(), true);
         let test_branch_2 = "test_branch_2".to_string();
-        cmd::branch(Some(test_branch_2.clone()), None, false, None, false); // branch2: test_file_1 exists
+        cmd::branch(Some(test_branch_2.clone()), None, Default::default()); // branch2: test_file_1 exists
 
         /* test 2: InvalidBranch */
         let result = switch_to("invalid_branch".to_string(), false);
@@ -174,5 +230,47 @@ mod test {
         assert!(!test_file_1.exists());
         assert!(tees_file_2.exists());
         assert!(status::changes_to_be_staged().is_empty() && status::changes_to_be_committed().is_empty());
+
+        /* test 7: 分层分支名 */
+        switch(None, Some("feature/login".to_string()), false);
+        assert!(matches!(head::current_head(), head::Head::Branch(branch) if branch == "feature/login"));
+        let result = switch_to(branch_master.clone(), false);
+        assert!(result.is_ok());
+        let result = switch_to("feature/login".to_string(), false);
+        assert!(result.is_ok());
+        assert!(matches!(head::current_head(), head::Head::Branch(branch) if branch == "feature/login"));
+    }
+
+    #[test]
+    fn test_switch_orphan() {
+        test::setup_with_empty_workdir();
+        let file = PathBuf::from("a.txt");
+        test::ensure_file(&file, Some("a"));
+        cmd::add(vec![], true, false);
+        cmd::commit("init".to_string(), false);
+        let master = head::current_head_commit();
+        test::ensure_file(Path::new("untracked.txt"), Some("u"));
+
+        assert!(orphan_to("master".to_string()).is_err()); // 已存在
+        assert!(orphan_to("gh-pages".to_string()).is_ok());
+        assert!(matches!(head::current_head(), head::Head::Branch(branch) if branch == "gh-pages"));
+        assert!(head::current_head_commit().is_empty());
+        assert!(!head::list_local_branches().contains(&"gh-pages".to_string()));
+        assert!(!file.exists());
+        assert!(Path::new("untracked.txt").exists());
+        assert!(status::changes_to_be_committed().is_empty());
+        cmd::log(vec![], false, None, false);
+        cmd::branch(None, None, cmd::branch::BranchOptions { list: true, verbose: 2, ..Default::default() });
+        status::status();
+
+        // 第一个commit没有parent
+        cmd::commit("docs".to_string(), true);
+        let docs = head::current_head_commit();
+        assert!(Commit::load(&docs).get_parent_hash().is_empty());
+        assert_eq!(head::get_branch_head(&"gh-pages".to_string()), docs);
+
+        assert!(switch_to("master".to_string(), false).is_ok());
+        assert_eq!(head::current_head_commit(), master);
+        assert!(file.exists());
     }
 }
@@ -0,0 +1,157 @@
+use crate::{
+    models::{head, Tag},
+    utils::{
+        revision,
+        util::{self, ObjectType},
+    },
+};
+
+/// tag指向的commit（附注标签会被解引用）
+pub fn tag_commit(name: &str) -> Option<String> {
+    revision::resolve_commit(&format!("refs/tags/{}", name)).ok()
+}
+
+/// tag名需符合ref命名规则，且不能以'-'开头
+fn check_tag_name(name: &str) -> bool {
+    !name.starts_with('-') && head::check_ref_name(name)
+}
+
+fn create_tag(name: &str, target: Option<String>, message: Option<String>, force: bool) {
+    if !check_tag_name(name) {
+        return println!("fatal: '{}' is not a valid tag name.", name);
+    }
+    let old = head::get_tag(name);
+    if !old.is_empty() && !force {
+        return println!("fatal: tag '{}' already exists", name);
+    }
+    // `a` 与 `a/b` 不能同时存在：refs/tags/a 不能既是文件又是目录
+    if let Some(exist) = head::list_tags()
+        .iter()
+        .find(|exist| name.starts_with(&format!("{}/", exist)) || exist.starts_with(&format!("{}/", name)))
+    {
+        return println!("fatal: 无法创建tag '{}'：与已存在的tag '{}' 冲突", name, exist);
+    }
+    let target = match revision::resolve(&target.unwrap_or("HEAD".to_string())) {
+        Ok(hash) => hash,
+        Err(err) => return println!("{}", err),
+    };
+    let object = match message {
+        Some(message) => Tag::new(name, &target, message).save(),
+        None => target,
+    };
+    head::update_tag(name, &object);
+    if !old.is_empty() && old != object {
+        println!("Updated tag '{}' (was {})", name, &old[..7]);
+    }
+}
+
+fn delete_tag(name: &str) {
+    let old = head::get_tag(name);
+    if old.is_empty() {
+        return println!("error: tag '{}' not found.", name);
+    }
+    head::delete_tag(name);
+    println!("Deleted tag '{}' (was {})", name, &old[..7]);
+}
+
+/// 检查附注标签：对象能被读取，且指向的对象存在
+fn verify_tag(name: &str) {
+    let object = head::get_tag(name);
+    if object.is_empty() {
+        return println!("error: tag '{}' not found.", name);
+    }
+    let object_type = util::check_object_type(object.clone());
+    if object_type != ObjectType::Tag {
+        let kind = format!("{:?}", object_type).to_lowercase();
+        return println!("error: {}: cannot verify a non-tag object of type {}.", name, kind);
+    }
+    let tag = Tag::load(&object);
+    let target_type = util::check_object_type(tag.get_object());
+    if target_type == ObjectType::Invalid {
+        return println!("error: {}: tag points to a missing object {}", name, tag.get_object());
+    }
+    println!("object {}", tag.get_object());
+    println!("type {}", format!("{:?}", target_type).to_lowercase());
+    println!("tag {}", tag.get_name());
+    println!("tagger {} {}", tag.get_tagger(), tag.get_date());
+    println!();
+    println!("{}", tag.get_message());
+}
+
+/** 创建、列出、删除、验证标签，保存在 refs/tags 下
+<br>指定 -a / -m 时创建附注标签（Tag对象，记录创建者、时间和说明），否则为直接指向commit的轻量标签
+ */
+pub fn tag(
+    name: Option<String>,
+    target: Option<String>,
+    annotate: bool,
+    message: Option<String>,
+    force: bool,
+    delete: bool,
+    verify: bool,
+) {
+    util::check_repo_exist();
+    let Some(name) = name else {
+        head::list_tags().iter().for_each(|tag| println!("{}", tag));
+        return;
+    };
+    if delete {
+        return delete_tag(&name);
+    }
+    if verify {
+        return verify_tag(&name);
+    }
+    if annotate && message.is_none() {
+        return println!("fatal: no tag message given, use -m <msg>");
+    }
+    create_tag(&name, target, message, force);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{commands as cmd, utils::test};
+
+    #[test]
+    fn test_tag() {
+        test::setup_with_empty_workdir();
+        cmd::commit("first".to_string(), true);
+        let first = head::current_head_commit();
+        cmd::commit("second".to_string(), true);
+        let second = head::current_head_commit();
+
+        // 轻量标签
+        tag(Some("v1".to_string()), Some("HEAD~1".to_string()), false, None, false, false, false);
+        assert_eq!(head::get_tag("v1"), first);
+        // 附注标签
+        tag(Some("v2".to_string()), None, true, Some("release 2".to_string()), false, false, false);
+        let object = head::get_tag("v2");
+        assert_eq!(util::check_object_type(object.clone()), ObjectType::Tag);
+        assert_eq!(Tag::load(&object).get_object(), second);
+        assert_eq!(head::list_tags(), vec!["v1", "v2"]);
+
+        // 可以在任何接受commit的地方使用
+        assert_eq!(revision::resolve_commit("v2").unwrap(), second);
+        assert_eq!(revision::resolve_commit("v2~1").unwrap(), first);
+        assert_eq!(revision::resolve("v2^{}").unwrap(), second);
+        assert_eq!(revision::resolve("v2^{tag}").unwrap(), object);
+        assert_eq!(tag_commit("v2"), Some(second.clone()));
+
+        // 已存在时需要 -f
+        tag(Some("v1".to_string()), None, false, None, false, false, false);
+        assert_eq!(head::get_tag("v1"), first);
+        tag(Some("v1".to_string()), None, false, None, true, false, false);
+        assert_eq!(head::get_tag("v1"), second);
+        tag(Some("bad name".to_string()), None, false, None, false, false, false);
+        assert_eq!(head::list_tags().len(), 2);
+
+        tag(Some("v1".to_string()), None, false, None, false, true, false);
+        assert_eq!(head::list_tags(), vec!["v2"]);
+
+        // 分层tag名不能与已有tag冲突
+        tag(Some("rel/v1".to_string()), None, false, None, false, false, false);
+        tag(Some("rel".to_string()), None, false, None, false, false, false);
+        tag(Some("v2/fix".to_string()), None, false, None, false, false, false);
+        assert_eq!(head::list_tags(), vec!["rel/v1", "v2"]);
+    }
+}
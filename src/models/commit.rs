@@ -29,6 +29,9 @@ impl Commit {
     pub fn get_date(&self) -> String {
         util::format_time(&self.date)
     }
+    pub fn get_time(&self) -> SystemTime {
+        self.date
+    }
     #[cfg(test)]
     pub fn get_tree_hash(&self) -> String {
         self.tree.clone()
@@ -45,6 +48,10 @@ impl Commit {
     pub fn get_author(&self) -> String {
         self.author.clone()
     }
+    /// 修改作者，e.g. cherry-pick时保留原作者
+    pub fn set_author(&mut self, author: String) {
+        self.author = author;
+    }
     // pub fn get_committer(&self) -> String {
     //     self.committer.clone()
     // }
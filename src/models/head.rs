@@ -1,4 +1,7 @@
-use crate::{models::Hash, utils::util};
+use crate::{
+    models::{packed_refs, reflog, Hash},
+    utils::util,
+};
 
 pub enum Head {
     Detached(String),
@@ -16,16 +19,53 @@ pub fn current_head() -> Head {
         Head::Detached(head_content)
     }
 }
-pub fn update_branch(branch_name: &String, commit_hash: &String) {
-    // 更新分支head
+/** 更新分支head，并以reason记录到分支的reflog；若为当前分支，同时记录到HEAD的reflog */
+pub fn update_branch(branch_name: &String, commit_hash: &String, reason: &str) {
+    let old_commit = get_branch_head(branch_name);
+    write_branch(branch_name, commit_hash);
+    reflog::append(&format!("refs/heads/{}", branch_name), &old_commit, commit_hash, reason);
+    if matches!(current_head(), Head::Branch(current) if &current == branch_name) {
+        reflog::append("HEAD", &old_commit, commit_hash, reason);
+    }
+}
+
+fn write_branch(branch_name: &String, commit_hash: &String) {
     let mut branch = util::get_storage_path().unwrap();
     branch.push("refs");
     branch.push("heads");
     branch.push(branch_name);
+    std::fs::create_dir_all(branch.parent().unwrap()).expect("无法创建refs/heads目录"); // e.g. feature/login
     std::fs::write(&branch, commit_hash)
         .unwrap_or_else(|_| panic!("无法写入branch in {:?} with {}", branch, commit_hash));
 }
 
+/** 复制分支，新分支继承原分支的reflog，并追加一条reason记录 */
+pub fn copy_branch(old_name: &String, new_name: &String, reason: &str) {
+    let commit = get_branch_head(old_name);
+    let entries = reflog::read(&format!("refs/heads/{}", old_name));
+    write_branch(new_name, &commit);
+    let new_ref = format!("refs/heads/{}", new_name);
+    reflog::write(&new_ref, &entries);
+    reflog::append(&new_ref, &commit, &commit, reason);
+}
+
+/** 重命名分支，reflog随之移动；若为当前分支，HEAD指向新分支 */
+pub fn rename_branch(old_name: &String, new_name: &String, reason: &str) {
+    let is_current = matches!(current_head(), Head::Branch(current) if &current == old_name);
+    let commit = get_branch_head(old_name);
+    let entries = reflog::read(&format!("refs/heads/{}", old_name));
+    delete_branch(old_name); // 先删除，`a` -> `a/b` 时需要
+    write_branch(new_name, &commit);
+    let new_ref = format!("refs/heads/{}", new_name);
+    reflog::write(&new_ref, &entries);
+    reflog::append(&new_ref, &commit, &commit, reason);
+    if is_current {
+        let head = util::get_storage_path().unwrap().join("HEAD");
+        std::fs::write(head, format!("ref: {}", new_ref)).expect("无法写入HEAD");
+        reflog::append("HEAD", &commit, &commit, reason);
+    }
+}
+
 pub fn get_branch_head(branch_name: &String) -> String {
     // 返回当前分支的commit hash
     let mut branch = util::get_storage_path().unwrap();
@@ -35,7 +75,8 @@ pub fn get_branch_head(branch_name: &String) -> String {
     if branch.exists() {
         std::fs::read_to_string(branch).expect("无法读取branch")
     } else {
-        "".to_string() // 分支不存在或者没有commit
+        // 分支不存在或者没有commit时为空
+        packed_refs::get(&format!("refs/heads/{}", branch_name)).unwrap_or_default()
     }
 }
 pub fn delete_branch(branch_name: &String) {
@@ -43,11 +84,26 @@ pub fn delete_branch(branch_name: &String) {
     branch.push("refs");
     branch.push("heads");
     branch.push(branch_name);
+    let packed = packed_refs::remove(&format!("refs/heads/{}", branch_name));
     if branch.exists() {
-        std::fs::remove_file(branch).expect("无法删除branch");
-    } else {
+        remove_loose_ref(&branch);
+    } else if !packed {
         panic!("branch file not exist");
     }
+    reflog::delete(&format!("refs/heads/{}", branch_name));
+}
+
+/// 删除refs/下的ref文件，以及因分层ref名而留下的空目录，e.g. refs/heads/feature
+fn remove_loose_ref(path: &std::path::Path) {
+    std::fs::remove_file(path).expect("无法删除ref");
+    let refs = util::get_storage_path().unwrap().join("refs");
+    let mut dir = path.parent();
+    while let Some(parent) = dir.filter(|parent| parent.parent() != Some(refs.as_path())) {
+        if std::fs::remove_dir(parent).is_err() {
+            break; // 非空
+        }
+        dir = parent.parent();
+    }
 }
 
 /**返回当前head指向的commit hash，如果是分支，则返回分支的commit hash */
@@ -62,52 +118,164 @@ pub fn current_head_commit() -> String {
     }
 }
 
-/** 将当前的head指向commit_hash，根据当前的head类型，更新不同的文件 */
-pub fn update_head_commit(commit_hash: &String) {
+/** 将当前的head指向commit_hash，根据当前的head类型，更新不同的文件；reason记录到reflog */
+pub fn update_head_commit(commit_hash: &String, reason: &str) {
     let head = current_head();
     match head {
         Head::Branch(branch_name) => {
-            update_branch(&branch_name, commit_hash);
+            update_branch(&branch_name, commit_hash, reason);
         }
-        Head::Detached(_) => {
+        Head::Detached(old_commit) => {
             let mut head = util::get_storage_path().unwrap();
             head.push("HEAD");
             std::fs::write(head, commit_hash).expect("无法写入HEAD");
+            reflog::append("HEAD", &old_commit, commit_hash, reason);
         }
     }
 }
 
-/** 列出本地的branch */
+/** 列出本地的branch，包括 `feature/login` 这样的分层分支名和packed-refs中的分支 */
 pub fn list_local_branches() -> Vec<String> {
-    let mut branches = Vec::new();
+    let mut branches = packed_refs::list("refs/heads/");
     let mut branch_dir = util::get_storage_path().unwrap();
     branch_dir.push("refs");
     branch_dir.push("heads");
     if branch_dir.exists() {
-        let entries = std::fs::read_dir(branch_dir).expect("无法读取branch");
-        for entry in entries {
-            let entry = entry.unwrap();
-            let branch_name = entry.file_name().into_string().unwrap();
-            branches.push(branch_name);
-        }
+        list_refs(&branch_dir, "", &mut branches);
     }
+    branches.sort();
+    branches.dedup();
     branches
 }
 
-/** 切换head到branch */
-pub fn change_head_to_branch(branch_name: &String) {
+/// 递归列出dir下的ref，名称以'/'分隔
+fn list_refs(dir: &std::path::Path, prefix: &str, refs: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries {
+        let entry = entry.unwrap();
+        let name = prefix.to_string() + &entry.file_name().into_string().unwrap();
+        if entry.path().is_dir() {
+            list_refs(&entry.path(), &(name + "/"), refs);
+        } else {
+            refs.push(name);
+        }
+    }
+}
+
+/** 检查ref名是否合法，规则同 `git check-ref-format`：
+- 不能为空或`@`，不能以'/'开头或结尾，不能包含 `//`、`..`、`@{`
+- 每一段不能以'.'开头，不能以`.lock`结尾；整体不能以'.'结尾
+- 不能包含控制字符、空白和 `~^:?*[\`
+ */
+pub fn check_ref_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "@"
+        && !name.ends_with('.')
+        && !name.contains("..")
+        && !name.contains("@{")
+        && !name
+            .chars()
+            .any(|c| c.is_control() || c.is_whitespace() || "~^:?*[\\".contains(c))
+        && name
+            .split('/')
+            .all(|part| !part.is_empty() && !part.starts_with('.') && !part.ends_with(".lock"))
+}
+
+fn tag_path(tag_name: &str) -> std::path::PathBuf {
+    util::get_storage_path().unwrap().join("refs").join("tags").join(tag_name)
+}
+
+/** 返回tag指向的对象（轻量标签为commit，附注标签为Tag对象），不存在时为空 */
+pub fn get_tag(tag_name: &str) -> String {
+    std::fs::read_to_string(tag_path(tag_name))
+        .map(|s| s.trim().to_string())
+        .ok()
+        .or_else(|| packed_refs::get(&format!("refs/tags/{}", tag_name)))
+        .unwrap_or_default()
+}
+
+pub fn update_tag(tag_name: &str, object_hash: &String) {
+    let path = tag_path(tag_name);
+    std::fs::create_dir_all(path.parent().unwrap()).expect("无法创建refs/tags目录");
+    std::fs::write(&path, object_hash).unwrap_or_else(|_| panic!("无法写入tag in {:?} with {}", path, object_hash));
+}
+
+/** 删除tag，loose ref和packed-refs中的都会被删除 */
+pub fn delete_tag(tag_name: &str) {
+    packed_refs::remove(&format!("refs/tags/{}", tag_name));
+    let path = tag_path(tag_name);
+    if path.exists() {
+        remove_loose_ref(&path);
+    }
+}
+
+/** 列出所有tag（包括packed-refs中的），按名称排序 */
+pub fn list_tags() -> Vec<String> {
+    let mut tags = packed_refs::list("refs/tags/");
+    list_refs(&util::get_storage_path().unwrap().join("refs").join("tags"), "", &mut tags);
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/** 将loose ref打包到packed-refs并删除ref文件
+<br>默认只打包tag，all时同时打包分支，同 `git pack-refs [--all]`
+ */
+pub fn pack_refs(all: bool) {
+    let mut packed = packed_refs::read();
+    let mut loose = Vec::new();
+    if all {
+        let mut branches = Vec::new();
+        list_refs(&util::get_storage_path().unwrap().join("refs").join("heads"), "", &mut branches);
+        loose.extend(
+            branches
+                .iter()
+                .map(|branch| (format!("refs/heads/{}", branch), get_branch_head(branch))),
+        );
+    }
+    let mut tags = Vec::new();
+    list_refs(&util::get_storage_path().unwrap().join("refs").join("tags"), "", &mut tags);
+    loose.extend(tags.iter().map(|tag| (format!("refs/tags/{}", tag), get_tag(tag))));
+
+    for (name, hash) in &loose {
+        packed.insert(name.clone(), hash.trim().to_string());
+    }
+    packed_refs::write(&packed);
+    for (name, _) in &loose {
+        remove_loose_ref(&util::get_storage_path().unwrap().join(name));
+    }
+}
+
+/** 切换head到branch，reason记录到HEAD的reflog */
+pub fn change_head_to_branch(branch_name: &String, reason: &str) {
+    let old_commit = current_head_commit();
     let mut head = util::get_storage_path().unwrap();
     head.push("HEAD");
     let branch_head = get_branch_head(branch_name);
     std::fs::write(head, format!("ref: refs/heads/{}", branch_name)).expect("无法写入HEAD");
-    update_head_commit(&branch_head);
+    reflog::append("HEAD", &old_commit, &branch_head, reason);
 }
 
-/** 切换head到非branchcommit */
-pub fn change_head_to_commit(commit_hash: &String) {
+/** 返回第n个之前所在的分支（或commit），n从1开始，即 `@{-n}`；根据HEAD的reflog中 `checkout: moving from A to B` 的记录 */
+pub fn previous_checkout(n: usize) -> Option<String> {
+    reflog::read("HEAD")
+        .into_iter()
+        .filter_map(|entry| {
+            let moving = entry.message.strip_prefix("checkout: moving from ")?;
+            moving.split_once(" to ").map(|(from, _)| from.to_string())
+        })
+        .nth(n.checked_sub(1)?)
+}
+
+/** 切换head到非branchcommit，reason记录到HEAD的reflog */
+pub fn change_head_to_commit(commit_hash: &String, reason: &str) {
+    let old_commit = current_head_commit();
     let mut head = util::get_storage_path().unwrap();
     head.push("HEAD");
     std::fs::write(head, commit_hash).expect("无法写入HEAD");
+    reflog::append("HEAD", &old_commit, commit_hash, reason);
 }
 
 #[cfg(test)]
@@ -123,7 +291,7 @@ mod test {
         assert!(branch_head.is_empty());
 
         let commit_hash = "1234567890".to_string();
-        super::update_branch(&branch_name, &commit_hash);
+        super::update_branch(&branch_name, &commit_hash, "test");
         let branch_head = super::get_branch_head(&branch_name);
         assert!(!branch_head.is_empty());
         assert!(branch_head == commit_hash);
@@ -134,20 +302,49 @@ mod test {
         test::setup_with_clean_mit();
         let branch_one = "test_branch".to_string() + &rand::random::<u32>().to_string();
         let branch_two = "test_branch".to_string() + &rand::random::<u32>().to_string();
-        head::update_branch(&branch_one, &"1234567890".to_string());
-        head::update_branch(&branch_two, &"1234567890".to_string());
+        head::update_branch(&branch_one, &"1234567890".to_string(), "test");
+        head::update_branch(&branch_two, &"1234567890".to_string(), "test");
 
         let branches = super::list_local_branches();
         assert!(branches.contains(&branch_one));
         assert!(branches.contains(&branch_two));
     }
 
+    #[test]
+    fn test_hierarchical_branch() {
+        test::setup_with_clean_mit();
+        head::update_branch(&"feature/login".to_string(), &"1234567890".to_string(), "test");
+        head::update_branch(&"feature/ui/menu".to_string(), &"1234567890".to_string(), "test");
+        let branches = super::list_local_branches();
+        assert!(branches.contains(&"feature/login".to_string()));
+        assert!(branches.contains(&"feature/ui/menu".to_string()));
+
+        super::delete_branch(&"feature/ui/menu".to_string());
+        assert!(!super::list_local_branches().contains(&"feature/ui/menu".to_string()));
+        let heads = crate::utils::util::get_storage_path().unwrap().join("refs/heads");
+        assert!(!heads.join("feature/ui").exists()); // 空目录被删除
+        assert!(heads.join("feature").exists());
+    }
+
+    #[test]
+    fn test_check_ref_name() {
+        for name in ["master", "feature/login", "v1.0", "a-b_c", "fix/#123"] {
+            assert!(super::check_ref_name(name), "{}", name);
+        }
+        for name in [
+            "", "@", "/a", "a/", "a//b", "a..b", ".a", "a/.b", "a.lock", "a.", "a b", "a~1", "a^", "a:b", "a?", "a*",
+            "a[", "a\\b", "a@{1}",
+        ] {
+            assert!(!super::check_ref_name(name), "{}", name);
+        }
+    }
+
     #[test]
     fn test_change_head_to_branch() {
         test::setup_with_clean_mit();
         let branch_name = "test_branch".to_string() + &rand::random::<u32>().to_string();
-        head::update_branch(&branch_name, &"1234567890".to_string());
-        super::change_head_to_branch(&branch_name);
+        head::update_branch(&branch_name, &"1234567890".to_string(), "test");
+        super::change_head_to_branch(&branch_name, "test");
         assert!(
             match super::current_head() {
                 super::Head::Branch(head_commit) => head_commit == branch_name,
@@ -161,7 +358,7 @@ mod test {
     fn test_change_head_to_commit() {
         test::setup_with_clean_mit();
         let commit_hash = "1234567890".to_string();
-        super::change_head_to_commit(&commit_hash);
+        super::change_head_to_commit(&commit_hash, "test");
         assert!(
             match super::current_head() {
                 super::Head::Detached(head_commit) => head_commit == commit_hash,
@@ -176,7 +373,7 @@ mod test {
         test::setup_with_clean_mit();
         let branch_name = "test_branch".to_string() + &rand::random::<u32>().to_string();
         let commit_hash = "1234567890".to_string();
-        super::update_branch(&branch_name, &commit_hash);
+        super::update_branch(&branch_name, &commit_hash, "test");
         let branch_head = super::get_branch_head(&branch_name);
         assert!(!branch_head.is_empty());
         assert!(branch_head == commit_hash);
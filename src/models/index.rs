@@ -9,6 +9,14 @@ use std::{
     time::SystemTime,
 };
 
+/// 合并冲突时记录的三个版本(git中的stage 1/2/3)，某一方不存在该文件时为None
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConflictStages {
+    pub base: Option<Hash>,
+    pub ours: Option<Hash>,
+    pub theirs: Option<Hash>,
+}
+
 // 文件元数据结构
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FileMetaData {
@@ -17,6 +25,9 @@ pub struct FileMetaData {
     pub created_time: SystemTime,  // 创建时间
     pub modified_time: SystemTime, // 修改时间
     pub mode: String,              // 文件模式
+    /// 未解决的合并冲突，重新add后清除
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stages: Option<ConflictStages>,
 }
 
 impl Default for FileMetaData {
@@ -27,6 +38,7 @@ impl Default for FileMetaData {
             created_time: SystemTime::now(),  // 或者使用 UNIX_EPOCH
             modified_time: SystemTime::now(), // 或者使用 UNIX_EPOCH
             mode: Default::default(),
+            stages: None,
         }
     }
 }
@@ -40,6 +52,7 @@ impl FileMetaData {
             created_time: meta.created().unwrap(),
             modified_time: meta.modified().unwrap(),
             mode: util::get_file_mode(file),
+            stages: None,
         }
     }
 }
@@ -65,7 +78,7 @@ impl Index {
     /// 单例模式，线程不安全，但是本程序默认单线程
     pub fn get_instance() -> &'static mut Index {
         static mut INSTANCE: Lazy<Index> = Lazy::new(Index::new); //延迟初始化，线程不安全
-        unsafe { &mut INSTANCE }
+        unsafe { &mut *std::ptr::addr_of_mut!(INSTANCE) }
     }
 
     /// 重置index，主要用于测试，防止单例模式的影响
@@ -203,6 +216,24 @@ impl Index {
         self.entries.clone()
     }
 
+    /// 记录文件的冲突版本
+    pub fn set_stages(&mut self, path: &Path, stages: ConflictStages) {
+        if let Some(data) = self.entries.get_mut(&Index::preprocess(path)) {
+            data.stages = Some(stages);
+        }
+    }
+
+    /// 所有未解决冲突的文件（绝对路径），按路径排序
+    pub fn get_conflicts(&self) -> Vec<(PathBuf, ConflictStages)> {
+        let mut conflicts: Vec<(PathBuf, ConflictStages)> = self
+            .entries
+            .iter()
+            .filter_map(|(path, data)| Some((path.clone(), data.stages.clone()?)))
+            .collect();
+        conflicts.sort_by(|a, b| a.0.cmp(&b.0));
+        conflicts
+    }
+
     #[cfg(test)]
     fn is_empty(&self) -> bool {
         self.entries.is_empty()
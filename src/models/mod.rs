@@ -3,11 +3,16 @@ pub use blob::Blob;
 pub mod commit;
 pub use commit::Commit;
 pub mod index;
+pub use index::ConflictStages;
 pub use index::FileMetaData;
 pub use index::Index;
 pub mod object;
 pub use object::Hash;
 pub mod head;
+pub mod packed_refs;
+pub mod reflog;
+pub mod tag;
+pub use tag::Tag;
 pub mod tree;
 
 pub use tree::Tree;
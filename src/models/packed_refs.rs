@@ -0,0 +1,89 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use crate::utils::util;
+
+use super::Hash;
+
+/// packed-refs 文件头：按ref名排序；不写入 `^<peeled>` 行，因此不声明peeled特性，读取方需要自行解引用附注标签
+const HEADER: &str = "# pack-refs with: sorted";
+
+fn packed_refs_path() -> PathBuf {
+    util::get_storage_path().unwrap().join("packed-refs")
+}
+
+/** 读取所有打包的ref：ref全名 -> hash；保存在 .mit/packed-refs，每行格式：`<hash> <ref>`
+<br>loose ref（refs/下的文件）优先于packed-refs中的同名ref
+ */
+pub fn read() -> BTreeMap<String, Hash> {
+    let content = fs::read_to_string(packed_refs_path()).unwrap_or_default();
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with('^')) // 注释、peeled行
+        .filter_map(|line| line.split_once(' '))
+        .map(|(hash, name)| (name.to_string(), hash.to_string()))
+        .collect()
+}
+
+/// 重写packed-refs，为空时删除文件
+pub fn write(refs: &BTreeMap<String, Hash>) {
+    if refs.is_empty() {
+        let _ = fs::remove_file(packed_refs_path());
+        return;
+    }
+    let mut content = format!("{}\n", HEADER);
+    for (name, hash) in refs {
+        content += &format!("{} {}\n", hash, name);
+    }
+    fs::write(packed_refs_path(), content).expect("无法写入packed-refs");
+}
+
+pub fn get(ref_name: &str) -> Option<Hash> {
+    read().remove(ref_name)
+}
+
+/// 列出以prefix开头的ref，返回去掉prefix后的名称，e.g. prefix为 `refs/heads/` 时返回分支名
+pub fn list(prefix: &str) -> Vec<String> {
+    read()
+        .into_keys()
+        .filter_map(|name| name.strip_prefix(prefix).map(String::from))
+        .collect()
+}
+
+/// 从packed-refs中删除ref，返回是否存在
+pub fn remove(ref_name: &str) -> bool {
+    let mut refs = read();
+    let removed = refs.remove(ref_name).is_some();
+    if removed {
+        write(&refs);
+    }
+    removed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test;
+
+    #[test]
+    fn test_packed_refs() {
+        test::setup_with_clean_mit();
+        assert!(read().is_empty());
+        let mut refs = BTreeMap::new();
+        refs.insert("refs/heads/master".to_string(), "aaa".to_string());
+        refs.insert("refs/heads/feature/a".to_string(), "bbb".to_string());
+        refs.insert("refs/tags/v1".to_string(), "ccc".to_string());
+        write(&refs);
+        assert!(fs::read_to_string(packed_refs_path())
+            .unwrap()
+            .starts_with(&format!("{}\n", HEADER)));
+        assert_eq!(read(), refs);
+        assert_eq!(get("refs/tags/v1"), Some("ccc".to_string()));
+        assert_eq!(list("refs/heads/"), vec!["feature/a", "master"]);
+
+        assert!(remove("refs/heads/master"));
+        assert!(!remove("refs/heads/master"));
+        assert_eq!(list("refs/heads/"), vec!["feature/a"]);
+        assert!(remove("refs/heads/feature/a") && remove("refs/tags/v1"));
+        assert!(!packed_refs_path().exists());
+    }
+}
@@ -0,0 +1,153 @@
+use std::{fs, io::Write, path::PathBuf};
+
+use crate::utils::{config, util};
+
+use super::Hash;
+
+/// 引用从未存在（或被切换到未出生的分支）时，old（new）使用全0
+const NULL_HASH: &str = "0000000000000000000000000000000000000000";
+
+/** 引用日志中的一条记录，保存在 .mit/logs/<ref>，e.g. logs/HEAD、logs/refs/heads/master
+<br>每行格式同git：`<old> <new> <identity> <timestamp> <tz>\t<reason>`，时区固定写为+0000（时间戳为UTC）
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReflogEntry {
+    pub old: Hash,
+    pub new: Hash,
+    pub identity: String,
+    pub timestamp: u64,
+    pub message: String,
+}
+
+impl ReflogEntry {
+    pub fn new(old: &str, new: &str, message: &str) -> ReflogEntry {
+        ReflogEntry {
+            old: if old.is_empty() {
+                NULL_HASH.to_string()
+            } else {
+                old.to_string()
+            },
+            new: if new.is_empty() {
+                NULL_HASH.to_string()
+            } else {
+                new.to_string()
+            },
+            identity: identity(),
+            timestamp: util::timestamp(),
+            message: message.to_string(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<ReflogEntry> {
+        let (info, message) = line.split_once('\t')?;
+        let (old, rest) = info.split_once(' ')?;
+        let (new, rest) = rest.split_once(' ')?;
+        let (mut identity, mut timestamp) = rest.rsplit_once(' ')?;
+        if timestamp.starts_with(['+', '-']) {
+            // 时区，旧的记录没有这一项
+            (identity, timestamp) = identity.rsplit_once(' ')?;
+        }
+        Some(ReflogEntry {
+            old: old.to_string(),
+            new: new.to_string(),
+            identity: identity.to_string(),
+            timestamp: timestamp.parse().ok()?,
+            message: message.to_string(),
+        })
+    }
+
+    fn to_line(&self) -> String {
+        format!("{} {} {} {} +0000\t{}\n", self.old, self.new, self.identity, self.timestamp, self.message)
+    }
+}
+
+/// 记录者：配置 user.name <user.email>，未配置时为mit
+fn identity() -> String {
+    let name = config::get("user.name").unwrap_or("mit".to_string());
+    match config::get("user.email") {
+        Some(email) => format!("{} <{}>", name, email),
+        None => name,
+    }
+}
+
+fn log_path(ref_name: &str) -> PathBuf {
+    util::get_storage_path().unwrap().join("logs").join(ref_name)
+}
+
+/// 追加一条记录，ref_name为 HEAD 或 refs/... 形式
+pub fn append(ref_name: &str, old: &str, new: &str, message: &str) {
+    let path = log_path(ref_name);
+    fs::create_dir_all(path.parent().unwrap()).expect("无法创建logs目录");
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .expect("无法写入reflog");
+    file.write_all(ReflogEntry::new(old, new, message).to_line().as_bytes())
+        .expect("无法写入reflog");
+}
+
+/// 读取所有记录，从新到旧，即第n个为 `<ref>@{n}`
+pub fn read(ref_name: &str) -> Vec<ReflogEntry> {
+    let content = fs::read_to_string(log_path(ref_name)).unwrap_or_default();
+    content.lines().rev().filter_map(ReflogEntry::parse).collect()
+}
+
+/// 重写所有记录（从新到旧），为空时删除日志文件
+pub fn write(ref_name: &str, entries: &[ReflogEntry]) {
+    let path = log_path(ref_name);
+    if entries.is_empty() {
+        let _ = fs::remove_file(path);
+        return;
+    }
+    fs::create_dir_all(path.parent().unwrap()).expect("无法创建logs目录");
+    let content: String = entries.iter().rev().map(ReflogEntry::to_line).collect();
+    fs::write(path, content).expect("无法写入reflog");
+}
+
+/// 删除引用的日志，以及因分层ref名而留下的空目录，e.g. 删除分支时
+pub fn delete(ref_name: &str) {
+    let path = log_path(ref_name);
+    if fs::remove_file(&path).is_err() {
+        return;
+    }
+    let logs = util::get_storage_path().unwrap().join("logs");
+    let mut dir = path.parent();
+    while let Some(parent) = dir.filter(|parent| parent.starts_with(&logs) && parent.parent() != Some(logs.as_path())) {
+        if fs::remove_dir(parent).is_err() {
+            break; // 非空
+        }
+        dir = parent.parent();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test;
+
+    #[test]
+    fn test_reflog() {
+        test::setup_with_clean_mit();
+        append("refs/heads/test", "", "aaa", "branch: Created from HEAD");
+        append("refs/heads/test", "aaa", "bbb", "commit: two words");
+        let entries = read("refs/heads/test");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].old, "aaa");
+        assert_eq!(entries[0].new, "bbb");
+        assert_eq!(entries[0].message, "commit: two words");
+        assert_eq!(entries[1].old, NULL_HASH);
+
+        let entry = ReflogEntry { identity: "A B <a@b>".to_string(), ..entries[0].clone() };
+        assert!(entry
+            .to_line()
+            .ends_with(&format!(" {} +0000\tcommit: two words\n", entry.timestamp)));
+        assert_eq!(ReflogEntry::parse(entry.to_line().trim_end()), Some(entry.clone()));
+        let without_tz = format!("aaa bbb A B <a@b> {}\tcommit: two words", entry.timestamp);
+        assert_eq!(ReflogEntry::parse(&without_tz), Some(entry.clone()));
+        write("refs/heads/test", &[entry]);
+        assert_eq!(read("refs/heads/test")[0].identity, "A B <a@b>");
+        write("refs/heads/test", &[]);
+        assert!(read("refs/heads/test").is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{store, util};
+
+use super::*;
+/*Tag
+* 附注标签(annotated tag)对象，记录了标签名、指向的对象、标签创建者、时间和说明。
+* 轻量标签(lightweight tag)没有Tag对象，refs/tags/<name>直接指向commit。
+*/
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Tag {
+    #[serde(skip)]
+    hash: Hash,
+    object: Hash, // 指向的对象，通常是commit
+    tag: String,  // 标签名
+    tagger: String,
+    date: SystemTime,
+    message: String,
+}
+
+impl Tag {
+    pub fn get_object(&self) -> Hash {
+        self.object.clone()
+    }
+    pub fn get_name(&self) -> String {
+        self.tag.clone()
+    }
+    pub fn get_tagger(&self) -> String {
+        self.tagger.clone()
+    }
+    pub fn get_date(&self) -> String {
+        util::format_time(&self.date)
+    }
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn new(name: &str, object: &Hash, message: String) -> Tag {
+        Tag {
+            hash: "".to_string(),
+            object: object.clone(),
+            tag: name.to_string(),
+            tagger: "mit".to_string(),
+            date: SystemTime::now(),
+            message,
+        }
+    }
+
+    pub fn load(hash: &String) -> Tag {
+        let s = store::Store::new();
+        let tag_data = s.load(hash);
+        let mut tag: Tag = serde_json::from_str(&tag_data).unwrap();
+        tag.hash = hash.clone();
+        tag
+    }
+
+    pub fn save(&mut self) -> String {
+        let s = store::Store::new();
+        let tag_data = serde_json::to_string_pretty(&self).unwrap();
+        let hash = s.save(&tag_data);
+        self.hash = hash.clone();
+        hash
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        models::Tag,
+        utils::{test, util},
+    };
+
+    #[test]
+    fn test_tag() {
+        test::setup_with_clean_mit();
+        let target = "1234567890".to_string();
+        let hash = Tag::new("v1.0", &target, "release".to_string()).save();
+        let tag = Tag::load(&hash);
+        assert_eq!(tag.get_object(), target);
+        assert_eq!(tag.get_name(), "v1.0");
+        assert_eq!(tag.get_message(), "release");
+        assert_eq!(util::check_object_type(hash), util::ObjectType::Tag);
+    }
+}
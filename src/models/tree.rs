@@ -1,4 +1,7 @@
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -108,6 +111,24 @@ impl Tree {
         hash
     }
 
+    /// 根据相对路径(to workdir)查找条目，支持子目录
+    pub fn find_entry(&self, path: &Path) -> Option<TreeEntry> {
+        let mut tree = self.clone();
+        let mut components = path.components().peekable();
+        while let Some(component) = components.next() {
+            let name = component.as_os_str().to_str()?;
+            let entry = tree.entries.iter().find(|e| e.name == name)?.clone();
+            if components.peek().is_none() {
+                return Some(entry);
+            }
+            if entry.filemode.0 != "tree" {
+                return None;
+            }
+            tree = Tree::load(&entry.object_hash);
+        }
+        None
+    }
+
     ///注：相对路径(to workdir)
     pub fn get_recursive_blobs(&self) -> Vec<(PathBuf, Hash)> {
         //TODO 返回HashMap
@@ -134,7 +155,7 @@ impl Tree {
 
 #[cfg(test)]
 mod test {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     use crate::{
         models::*,
@@ -198,5 +219,10 @@ mod test {
         assert!(blobs.len() == test_files.len());
         assert!(blobs.contains(&(PathBuf::from(test_files[0]), test_blobs[0].get_hash())));
         assert!(blobs.contains(&(PathBuf::from(test_files[1]), test_blobs[1].get_hash())));
+
+        let entry = loaded_tree.find_entry(Path::new("mit_src/a.txt")).unwrap();
+        assert_eq!(entry.object_hash, test_blobs[1].get_hash());
+        assert_eq!(loaded_tree.find_entry(Path::new("mit_src")).unwrap().filemode.0, "tree");
+        assert!(loaded_tree.find_entry(Path::new("b.txt/a.txt")).is_none());
     }
 }
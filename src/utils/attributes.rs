@@ -0,0 +1,140 @@
+use std::{fs, path::Path};
+
+use super::util;
+
+/// 属性文件名，位于workdir根目录，格式同 .gitattributes
+const ATTRIBUTES_FILE: &str = ".mitattributes";
+
+/// 一行规则：pattern及其属性 (name, value)
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: String,
+    attrs: Vec<(String, String)>,
+}
+
+/**
+.mitattributes：按路径为文件设置属性，e.g.
+```text
+*.lock      merge=ours
+CHANGELOG.md merge=union
+*.bin       -merge
+```
+`attr`为"set"，`-attr`为"unset"，`attr=value`为value；同一文件匹配多行时以后面的为准
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Attributes {
+    rules: Vec<Rule>,
+}
+
+impl Attributes {
+    pub fn parse(content: &str) -> Attributes {
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next().unwrap().to_string();
+            let attrs = parts
+                .map(|attr| match attr.split_once('=') {
+                    Some((name, value)) => (name.to_string(), value.to_string()),
+                    None => match attr.strip_prefix('-') {
+                        Some(name) => (name.to_string(), "unset".to_string()),
+                        None => (attr.to_string(), "set".to_string()),
+                    },
+                })
+                .collect();
+            rules.push(Rule { pattern, attrs });
+        }
+        Attributes { rules }
+    }
+
+    /// 加载workdir根目录下的.mitattributes，不存在时为空
+    pub fn load() -> Attributes {
+        let path = util::get_working_dir().unwrap().join(ATTRIBUTES_FILE);
+        Attributes::parse(&fs::read_to_string(path).unwrap_or_default())
+    }
+
+    /// 获取文件的属性值，path为相对workdir的路径
+    pub fn get(&self, path: &Path, name: &str) -> Option<String> {
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.rules
+            .iter()
+            .filter(|rule| matches_path(&rule.pattern, &path))
+            .flat_map(|rule| rule.attrs.iter())
+            .rfind(|(attr, _)| attr == name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+/// pattern不包含'/'时匹配文件名，否则匹配从根目录开始的完整路径
+fn matches_path(pattern: &str, path: &str) -> bool {
+    match pattern.strip_prefix('/') {
+        Some(pattern) => glob_match(pattern, path),
+        None if pattern.contains('/') => glob_match(pattern, path),
+        None => glob_match(pattern, path.rsplit('/').next().unwrap_or(path)),
+    }
+}
+
+/// 简单的glob匹配：`*`不跨越'/'，`**`可以跨越目录，`?`匹配单个字符
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_from(&pattern, &text)
+}
+
+fn glob_match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            // `**/` 可以匹配零个目录
+            let rest = &pattern[2..];
+            if let Some(after_slash) = rest.strip_prefix(&['/']) {
+                if glob_match_from(after_slash, text) {
+                    return true;
+                }
+            }
+            (0..=text.len()).any(|i| glob_match_from(rest, &text[i..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match_from(rest, &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => !text.is_empty() && text[0] != '/' && glob_match_from(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && glob_match_from(&pattern[1..], &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attributes() {
+        assert!(glob_match("*.lock", "Cargo.lock"));
+        assert!(!glob_match("*.lock", "dir/Cargo.lock"));
+        assert!(glob_match("docs/**", "docs/a/b.md"));
+        assert!(glob_match("**/gen/*.rs", "gen/a.rs"));
+        assert!(glob_match("**/gen/*.rs", "src/gen/a.rs"));
+        assert!(!glob_match("src/*.rs", "src/gen/a.rs"));
+        assert!(glob_match("a?c", "abc"));
+
+        let attributes =
+            Attributes::parse("# comment\n*.lock merge=ours\nlib/*.lock -merge\nCHANGELOG.md merge=union text\n");
+        assert_eq!(attributes.get(Path::new("Cargo.lock"), "merge"), Some("ours".to_string()));
+        assert_eq!(attributes.get(Path::new("sub/Cargo.lock"), "merge"), Some("ours".to_string()));
+        assert_eq!(attributes.get(Path::new("lib/Cargo.lock"), "merge"), Some("unset".to_string()));
+        assert_eq!(attributes.get(Path::new("CHANGELOG.md"), "merge"), Some("union".to_string()));
+        assert_eq!(attributes.get(Path::new("CHANGELOG.md"), "text"), Some("set".to_string()));
+        assert_eq!(attributes.get(Path::new("README.md"), "merge"), None);
+    }
+}
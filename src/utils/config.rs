@@ -0,0 +1,246 @@
+use std::{fs, path::PathBuf};
+
+use super::util;
+
+/// 配置段: `[section]` 或 `[section "subsection"]`
+#[derive(Debug, Clone)]
+struct Section {
+    name: String,
+    sub: Option<String>,
+    entries: Vec<(String, String)>,
+}
+
+/**
+.mit/config 配置文件，格式与git config相同（简化版INI）
+<br>key的形式为 `section.name` 或 `section.subsection.name`，section与name不区分大小写
+```ini
+[diff]
+    algorithm = patience
+[branch "main"]
+    merge = refs/heads/dev
+```
+<br>全局配置保存在 ~/.mitconfig，e.g. `init.defaultBranch`
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    path: PathBuf,
+    sections: Vec<Section>,
+}
+
+/// 拆分key为 (section, subsection, name)
+fn split_key(key: &str) -> Option<(String, Option<String>, String)> {
+    let first = key.find('.')?;
+    let last = key.rfind('.')?;
+    let section = key[..first].to_lowercase();
+    let name = key[last + 1..].to_lowercase();
+    if section.is_empty() || name.is_empty() {
+        return None;
+    }
+    let sub = if first == last {
+        None
+    } else {
+        Some(key[first + 1..last].to_string())
+    };
+    Some((section, sub, name))
+}
+
+/// 拆分段名 `section` 或 `section.subsection`
+fn split_section(name: &str) -> (String, Option<String>) {
+    match name.split_once('.') {
+        Some((section, sub)) => (section.to_lowercase(), Some(sub.to_string())),
+        None => (name.to_lowercase(), None),
+    }
+}
+
+impl Config {
+    /// 从文件加载，文件不存在时为空配置
+    pub fn load_from(path: PathBuf) -> Config {
+        let mut config = Config { path, sections: Vec::new() };
+        if let Ok(content) = fs::read_to_string(&config.path) {
+            config.parse(&content);
+        }
+        config
+    }
+
+    /// 加载当前仓库的配置 .mit/config
+    pub fn load() -> Config {
+        Config::load_from(util::get_storage_path().unwrap().join("config"))
+    }
+
+    /// 加载全局配置 ~/.mitconfig，找不到home目录时为空配置
+    pub fn load_global() -> Config {
+        match std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+            Some(home) => Config::load_from(PathBuf::from(home).join(".mitconfig")),
+            None => Config::default(),
+        }
+    }
+
+    fn parse(&mut self, content: &str) {
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                let header = line[1..line.len() - 1].trim();
+                let (name, sub) = match header.split_once(char::is_whitespace) {
+                    Some((name, sub)) => (name, Some(sub.trim().trim_matches('"').to_string())),
+                    None => (header, None),
+                };
+                self.sections
+                    .push(Section { name: name.to_lowercase(), sub, entries: Vec::new() });
+            } else if let Some(section) = self.sections.last_mut() {
+                let (name, value) = match line.split_once('=') {
+                    Some((name, value)) => (name.trim(), value.trim().trim_matches('"')),
+                    None => (line, "true"), // 只有key的布尔值
+                };
+                section.entries.push((name.to_lowercase(), value.to_string()));
+            }
+        }
+    }
+
+    fn find_section(&self, name: &str, sub: &Option<String>) -> Option<usize> {
+        self.sections.iter().position(|s| s.name == name && &s.sub == sub)
+    }
+
+    /// 获取配置值，同名key以最后一个为准
+    pub fn get(&self, key: &str) -> Option<String> {
+        let (section, sub, name) = split_key(key)?;
+        self.sections
+            .iter()
+            .filter(|s| s.name == section && s.sub == sub)
+            .flat_map(|s| s.entries.iter())
+            .rfind(|(k, _)| *k == name)
+            .map(|(_, v)| v.clone())
+    }
+
+    /// 设置配置值（覆盖已有值），需要手动[Config::save]；key不包含section时返回false
+    pub fn set(&mut self, key: &str, value: &str) -> bool {
+        let (section, sub, name) = match split_key(key) {
+            Some(parts) => parts,
+            None => return false,
+        };
+        let idx = match self.find_section(&section, &sub) {
+            Some(idx) => idx,
+            None => {
+                self.sections.push(Section { name: section, sub, entries: Vec::new() });
+                self.sections.len() - 1
+            }
+        };
+        let entries = &mut self.sections[idx].entries;
+        match entries.iter_mut().find(|(k, _)| *k == name) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => entries.push((name, value.to_string())),
+        }
+        true
+    }
+
+    /// 删除配置值，返回是否存在
+    pub fn unset(&mut self, key: &str) -> bool {
+        let (section, sub, name) = match split_key(key) {
+            Some(parts) => parts,
+            None => return false,
+        };
+        let mut found = false;
+        for s in self.sections.iter_mut().filter(|s| s.name == section && s.sub == sub) {
+            let before = s.entries.len();
+            s.entries.retain(|(k, _)| *k != name);
+            found |= before != s.entries.len();
+        }
+        self.sections.retain(|s| !s.entries.is_empty());
+        found
+    }
+
+    /// 删除整个配置段，e.g. `branch.main`，返回是否存在
+    pub fn remove_section(&mut self, name: &str) -> bool {
+        let (section, sub) = split_section(name);
+        let before = self.sections.len();
+        self.sections.retain(|s| !(s.name == section && s.sub == sub));
+        before != self.sections.len()
+    }
+
+    /// 复制配置段，已存在的目标段会被覆盖，e.g. 复制分支时 `branch.a` -> `branch.b`
+    pub fn copy_section(&mut self, from: &str, to: &str) {
+        let (section, from_sub) = split_section(from);
+        let (to_section, to_sub) = split_section(to);
+        let copies: Vec<Section> = self
+            .sections
+            .iter()
+            .filter(|s| s.name == section && s.sub == from_sub)
+            .map(|s| Section {
+                name: to_section.clone(),
+                sub: to_sub.clone(),
+                entries: s.entries.clone(),
+            })
+            .collect();
+        self.remove_section(to);
+        self.sections.extend(copies);
+    }
+
+    /// 列出所有配置项 (key, value)
+    pub fn list(&self) -> Vec<(String, String)> {
+        let mut items = Vec::new();
+        for s in &self.sections {
+            let prefix = match &s.sub {
+                Some(sub) => format!("{}.{}", s.name, sub),
+                None => s.name.clone(),
+            };
+            for (k, v) in &s.entries {
+                items.push((format!("{}.{}", prefix, k), v.clone()));
+            }
+        }
+        items
+    }
+
+    pub fn save(&self) {
+        let mut content = String::new();
+        for s in &self.sections {
+            match &s.sub {
+                Some(sub) => content.push_str(&format!("[{} \"{}\"]\n", s.name, sub)),
+                None => content.push_str(&format!("[{}]\n", s.name)),
+            }
+            for (k, v) in &s.entries {
+                content.push_str(&format!("\t{} = {}\n", k, v));
+            }
+        }
+        fs::write(&self.path, content).expect("无法写入config");
+        util::clear_bare_cache();
+    }
+}
+
+/// 读取配置项，当前仓库的配置优先于全局配置 ~/.mitconfig
+pub fn get(key: &str) -> Option<String> {
+    Config::load().get(key).or_else(|| Config::load_global().get(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test;
+
+    #[test]
+    fn test_set_get() {
+        test::setup_with_clean_mit();
+        let mut config = Config::load();
+        config.set("diff.algorithm", "patience");
+        config.set("branch.feature/a.merge", "refs/heads/master");
+        config.save();
+        assert_eq!(get("diff.algorithm"), Some("patience".to_string()));
+        assert_eq!(get("DIFF.Algorithm"), Some("patience".to_string()));
+        assert_eq!(get("branch.feature/a.merge"), Some("refs/heads/master".to_string()));
+        assert_eq!(get("branch.feature.merge"), None);
+        assert!(!config.set("foo", "bar"));
+
+        let mut config = Config::load();
+        assert!(config.unset("diff.algorithm"));
+        config.save();
+        assert_eq!(get("diff.algorithm"), None);
+
+        let mut config = Config::load();
+        config.copy_section("branch.feature/a", "branch.b");
+        assert_eq!(config.get("branch.b.merge"), Some("refs/heads/master".to_string()));
+        assert!(config.remove_section("branch.feature/a"));
+        assert!(!config.remove_section("branch.feature/a"));
+        assert_eq!(config.get("branch.feature/a.merge"), None);
+    }
+}
@@ -0,0 +1,680 @@
+use std::collections::HashMap;
+
+use super::config;
+
+/**
+可插拔的行级diff引擎，供diff、merge、blame等复用
+<br>所有算法都先将行内容映射为整数id（可选忽略空白），再在id序列上计算匹配
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum DiffAlgorithm {
+    /// 经典的Myers O(ND)算法，结果最短但对重构后的代码可读性较差
+    #[default]
+    #[value(alias = "default")]
+    Myers,
+    /// 以两侧都只出现一次的行作为锚点
+    Patience,
+    /// patience的扩展：以出现次数最少的行作为锚点
+    Histogram,
+}
+
+impl DiffAlgorithm {
+    pub fn from_name(name: &str) -> Option<DiffAlgorithm> {
+        match name.to_lowercase().as_str() {
+            "myers" | "default" | "minimal" => Some(DiffAlgorithm::Myers),
+            "patience" => Some(DiffAlgorithm::Patience),
+            "histogram" => Some(DiffAlgorithm::Histogram),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiffOptions {
+    pub algorithm: DiffAlgorithm,
+    /// -w 比较时忽略所有空白
+    pub ignore_all_space: bool,
+    /// -b 忽略空白数量的变化
+    pub ignore_space_change: bool,
+    /// 忽略只包含空行的更改
+    pub ignore_blank_lines: bool,
+}
+
+impl DiffOptions {
+    /// 默认选项，算法取自配置 `diff.algorithm`
+    pub fn from_config() -> DiffOptions {
+        let algorithm = config::get("diff.algorithm").and_then(|name| DiffAlgorithm::from_name(&name));
+        DiffOptions {
+            algorithm: algorithm.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// 根据空白选项规范化一行，用于比较
+    fn normalize(&self, line: &str) -> String {
+        if self.ignore_all_space {
+            line.chars().filter(|c| !c.is_whitespace()).collect()
+        } else if self.ignore_space_change {
+            let leading = if line.starts_with(char::is_whitespace) { " " } else { "" };
+            let body = line.split_whitespace().collect::<Vec<_>>().join(" ");
+            if body.is_empty() {
+                body
+            } else {
+                format!("{}{}", leading, body)
+            }
+        } else {
+            line.to_string()
+        }
+    }
+}
+
+/// 行级编辑操作，参数为行号(0-based)：Equal(old, new)，Delete(old)，Insert(new)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// 统一diff格式中的一行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Context(String),
+    Delete(String),
+    Insert(String),
+}
+
+/// 统一diff格式中的一个hunk，行号从1开始
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+impl Hunk {
+    pub fn header(&self) -> String {
+        format!("@@ -{},{} +{},{} @@", self.old_start, self.old_len, self.new_start, self.new_len)
+    }
+}
+
+/// 按行拆分文本（不包含换行符）
+pub fn split_lines(text: &str) -> Vec<&str> {
+    text.lines().collect()
+}
+
+/// 计算两组行之间的编辑脚本
+pub fn diff_lines<S: AsRef<str>>(old: &[S], new: &[S], options: &DiffOptions) -> Vec<Edit> {
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut intern = |line: &S| {
+        let key = options.normalize(line.as_ref());
+        let next = ids.len();
+        *ids.entry(key).or_insert(next)
+    };
+    let a: Vec<usize> = old.iter().map(&mut intern).collect();
+    let b: Vec<usize> = new.iter().map(&mut intern).collect();
+
+    let mut matches = Vec::new();
+    diff_range(&a, &b, 0, 0, options.algorithm, &mut matches);
+    matches_to_edits(&matches, a.len(), b.len())
+}
+
+/// 计算两段文本的统一diff hunks
+pub fn diff_hunks(old: &str, new: &str, options: &DiffOptions, context: usize) -> Vec<Hunk> {
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+    let edits = diff_lines(&old_lines, &new_lines, options);
+    build_hunks(&edits, &old_lines, &new_lines, options, context)
+}
+
+/// 计算两段文本的相似度(0~100)：公共行数占两侧总行数的比例
+pub fn similarity(old: &str, new: &str) -> u32 {
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+    let total = old_lines.len() + new_lines.len();
+    if total == 0 {
+        return 100;
+    }
+    let edits = diff_lines(&old_lines, &new_lines, &DiffOptions::default());
+    let common = edits.iter().filter(|e| matches!(e, Edit::Equal(..))).count();
+    (common * 2 * 100 / total) as u32
+}
+
+/// 三路合并的结果块
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeChunk {
+    /// 无冲突，可以直接使用
+    Clean(Vec<String>),
+    /// 双方对同一区域做了不同的修改
+    Conflict { base: Vec<String>, ours: Vec<String>, theirs: Vec<String> },
+}
+
+/// base中每一行在另一侧匹配到的行号
+fn line_mapping(base: &[&str], other: &[&str], options: &DiffOptions) -> Vec<Option<usize>> {
+    let mut mapping = vec![None; base.len()];
+    for edit in diff_lines(base, other, options) {
+        if let Edit::Equal(i, j) = edit {
+            mapping[i] = Some(j);
+        }
+    }
+    mapping
+}
+
+/** 行级三路合并(diff3)：分别计算base到两侧的diff，以两侧都未修改的base行为同步点划分区块
+<br>不稳定区块中只有一侧修改时取该侧，双方修改相同时取任意一侧，否则为冲突
+ */
+pub fn merge3(base: &str, ours: &str, theirs: &str, options: &DiffOptions) -> Vec<MergeChunk> {
+    let (base, ours, theirs) = (split_lines(base), split_lines(ours), split_lines(theirs));
+    let to_owned = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect::<Vec<String>>();
+    let ours_map = line_mapping(&base, &ours, options);
+    let theirs_map = line_mapping(&base, &theirs, options);
+
+    let mut chunks: Vec<MergeChunk> = Vec::new();
+    let push_clean = |chunks: &mut Vec<MergeChunk>, lines: &[&str]| {
+        if lines.is_empty() {
+            return;
+        }
+        match chunks.last_mut() {
+            Some(MergeChunk::Clean(last)) => last.extend(to_owned(lines)),
+            _ => chunks.push(MergeChunk::Clean(to_owned(lines))),
+        }
+    };
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    while b < base.len() || o < ours.len() || t < theirs.len() {
+        // 稳定区块：三方一致
+        let mut n = 0;
+        while b + n < base.len() && ours_map[b + n] == Some(o + n) && theirs_map[b + n] == Some(t + n) {
+            n += 1;
+        }
+        if n > 0 {
+            push_clean(&mut chunks, &base[b..b + n]);
+            (b, o, t) = (b + n, o + n, t + n);
+            continue;
+        }
+        // 不稳定区块：直到下一个两侧都匹配的base行
+        let (nb, no, nt) = (b..base.len())
+            .find_map(|i| Some((i, ours_map[i]?, theirs_map[i]?)))
+            .unwrap_or((base.len(), ours.len(), theirs.len()));
+        let (base_part, ours_part, theirs_part) = (&base[b..nb], &ours[o..no], &theirs[t..nt]);
+        if ours_part == theirs_part || base_part == theirs_part {
+            push_clean(&mut chunks, ours_part);
+        } else if base_part == ours_part {
+            push_clean(&mut chunks, theirs_part);
+        } else {
+            chunks.push(MergeChunk::Conflict {
+                base: to_owned(base_part),
+                ours: to_owned(ours_part),
+                theirs: to_owned(theirs_part),
+            });
+        }
+        (b, o, t) = (nb, no, nt);
+    }
+    chunks
+}
+
+/// 将匹配的行对转换为编辑脚本
+fn matches_to_edits(matches: &[(usize, usize)], n: usize, m: usize) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for &(mi, mj) in matches {
+        edits.extend((i..mi).map(Edit::Delete));
+        edits.extend((j..mj).map(Edit::Insert));
+        edits.push(Edit::Equal(mi, mj));
+        i = mi + 1;
+        j = mj + 1;
+    }
+    edits.extend((i..n).map(Edit::Delete));
+    edits.extend((j..m).map(Edit::Insert));
+    edits
+}
+
+/// 在子区间上计算匹配，结果为全局行号(加上偏移)，按顺序追加到out
+fn diff_range(
+    a: &[usize],
+    b: &[usize],
+    a_off: usize,
+    b_off: usize,
+    algo: DiffAlgorithm,
+    out: &mut Vec<(usize, usize)>,
+) {
+    // 公共前缀 & 后缀对所有算法都成立，先剥离
+    let prefix = a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    out.extend((0..prefix).map(|k| (a_off + k, b_off + k)));
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    if !a_mid.is_empty() && !b_mid.is_empty() {
+        let (a_off, b_off) = (a_off + prefix, b_off + prefix);
+        match algo {
+            DiffAlgorithm::Myers => myers(a_mid, b_mid, a_off, b_off, out),
+            DiffAlgorithm::Patience => patience(a_mid, b_mid, a_off, b_off, out),
+            DiffAlgorithm::Histogram => histogram(a_mid, b_mid, a_off, b_off, out),
+        }
+    }
+
+    let (a_end, b_end) = (a_off + a.len(), b_off + b.len());
+    out.extend((0..suffix).rev().map(|k| (a_end - 1 - k, b_end - 1 - k)));
+}
+
+/** Myers算法（线性空间的分治版本）：寻找中间snake，递归处理两侧
+<br>@see <a href="http://www.xmailserver.org/diff2.pdf">An O(ND) Difference Algorithm and Its Variations</a>
+ */
+fn myers(a: &[usize], b: &[usize], a_off: usize, b_off: usize, out: &mut Vec<(usize, usize)>) {
+    match middle_snake(a, b) {
+        Some((x, y)) if (x, y) != (0, 0) && (x, y) != (a.len(), b.len()) => {
+            diff_range(&a[..x], &b[..y], a_off, b_off, DiffAlgorithm::Myers, out);
+            diff_range(&a[x..], &b[y..], a_off + x, b_off + y, DiffAlgorithm::Myers, out);
+        }
+        _ => {} // 两侧完全不同（或无法再分割）：没有任何匹配
+    }
+}
+
+/// 寻找最短编辑路径上的中间snake，返回分割点 (x, y)
+fn middle_snake(a: &[usize], b: &[usize]) -> Option<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = ((n + m + 1) / 2) as usize + 1;
+    let offset = max as isize;
+    let mut vf = vec![0isize; 2 * max + 2];
+    let mut vb = vec![0isize; 2 * max + 2];
+    let delta = n - m;
+    let odd = delta & 1 == 1;
+    let idx = |k: isize| (k + offset) as usize;
+
+    for d in 0..max as isize {
+        // 正向
+        let mut k = -d;
+        while k <= d {
+            let mut x = if k == -d || (k != d && vf[idx(k - 1)] < vf[idx(k + 1)]) {
+                vf[idx(k + 1)]
+            } else {
+                vf[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            vf[idx(k)] = x;
+            let rk = delta - k; // 对应的反向对角线
+            if odd && rk.abs() < d && x + vb[idx(rk)] >= n {
+                return if x0 >= 0 && y0 >= 0 {
+                    Some((x0 as usize, y0 as usize))
+                } else {
+                    Some((x as usize, y as usize))
+                };
+            }
+            k += 2;
+        }
+        // 反向 (从末尾开始)
+        let mut k = -d;
+        while k <= d {
+            let mut x = if k == -d || (k != d && vb[idx(k - 1)] < vb[idx(k + 1)]) {
+                vb[idx(k + 1)]
+            } else {
+                vb[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            vb[idx(k)] = x;
+            let fk = delta - k;
+            if !odd && fk.abs() <= d && x + vf[idx(fk)] >= n {
+                return Some(((n - x) as usize, (m - y) as usize));
+            }
+            k += 2;
+        }
+    }
+    None
+}
+
+/// patience：两侧都只出现一次的行构成候选，取最长递增子序列作为锚点，递归处理锚点之间
+fn patience(a: &[usize], b: &[usize], a_off: usize, b_off: usize, out: &mut Vec<(usize, usize)>) {
+    let mut counts: HashMap<usize, (usize, usize, usize)> = HashMap::new(); // id -> (count_a, count_b, pos_a)
+    for (i, id) in a.iter().enumerate() {
+        let entry = counts.entry(*id).or_insert((0, 0, i));
+        entry.0 += 1;
+    }
+    for id in b.iter() {
+        if let Some(entry) = counts.get_mut(id) {
+            entry.1 += 1;
+        }
+    }
+    let candidates: Vec<(usize, usize)> = b
+        .iter()
+        .enumerate()
+        .filter_map(|(j, id)| match counts.get(id) {
+            Some(&(1, 1, i)) => Some((i, j)),
+            _ => None,
+        })
+        .collect();
+    if candidates.is_empty() {
+        return myers(a, b, a_off, b_off, out);
+    }
+
+    let anchors = longest_increasing(&candidates);
+    let (mut i, mut j) = (0, 0);
+    for (ai, bj) in anchors {
+        diff_range(&a[i..ai], &b[j..bj], a_off + i, b_off + j, DiffAlgorithm::Patience, out);
+        out.push((a_off + ai, b_off + bj));
+        i = ai + 1;
+        j = bj + 1;
+    }
+    diff_range(&a[i..], &b[j..], a_off + i, b_off + j, DiffAlgorithm::Patience, out);
+}
+
+/// 候选按b中位置排列，求a中位置的最长递增子序列（patience sorting）
+fn longest_increasing(candidates: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut piles: Vec<usize> = Vec::new(); // 每堆顶部元素在candidates中的下标
+    let mut prev: Vec<Option<usize>> = vec![None; candidates.len()];
+    for (idx, &(ai, _)) in candidates.iter().enumerate() {
+        let pos = piles.partition_point(|&top| candidates[top].0 < ai);
+        if pos > 0 {
+            prev[idx] = Some(piles[pos - 1]);
+        }
+        if pos == piles.len() {
+            piles.push(idx);
+        } else {
+            piles[pos] = idx;
+        }
+    }
+    let mut result = Vec::new();
+    let mut cur = piles.last().copied();
+    while let Some(idx) = cur {
+        result.push(candidates[idx]);
+        cur = prev[idx];
+    }
+    result.reverse();
+    result
+}
+
+/// histogram中出现次数超过该值的行不作为锚点
+const HISTOGRAM_MAX_CHAIN: usize = 64;
+
+/// histogram：选择a中出现次数最少的公共行作为种子，向两侧扩展为最长公共区域，递归处理两侧
+fn histogram(a: &[usize], b: &[usize], a_off: usize, b_off: usize, out: &mut Vec<(usize, usize)>) {
+    let mut occurrences: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, id) in a.iter().enumerate() {
+        occurrences.entry(*id).or_default().push(i);
+    }
+
+    // (count, len, a_start, b_start)
+    let mut best: Option<(usize, usize, usize, usize)> = None;
+    for (j, id) in b.iter().enumerate() {
+        let occ = match occurrences.get(id) {
+            Some(occ) if occ.len() <= HISTOGRAM_MAX_CHAIN => occ,
+            _ => continue,
+        };
+        let count = occ.len();
+        if matches!(best, Some((best_count, ..)) if count > best_count) {
+            continue;
+        }
+        for &i in occ {
+            let (mut s_a, mut s_b) = (i, j);
+            while s_a > 0 && s_b > 0 && a[s_a - 1] == b[s_b - 1] {
+                s_a -= 1;
+                s_b -= 1;
+            }
+            let (mut e_a, mut e_b) = (i + 1, j + 1);
+            while e_a < a.len() && e_b < b.len() && a[e_a] == b[e_b] {
+                e_a += 1;
+                e_b += 1;
+            }
+            let len = e_a - s_a;
+            let better = match best {
+                None => true,
+                Some((best_count, best_len, ..)) => count < best_count || (count == best_count && len > best_len),
+            };
+            if better {
+                best = Some((count, len, s_a, s_b));
+            }
+        }
+    }
+
+    match best {
+        None => myers(a, b, a_off, b_off, out),
+        Some((_, len, s_a, s_b)) => {
+            diff_range(&a[..s_a], &b[..s_b], a_off, b_off, DiffAlgorithm::Histogram, out);
+            out.extend((0..len).map(|k| (a_off + s_a + k, b_off + s_b + k)));
+            let (e_a, e_b) = (s_a + len, s_b + len);
+            diff_range(&a[e_a..], &b[e_b..], a_off + e_a, b_off + e_b, DiffAlgorithm::Histogram, out);
+        }
+    }
+}
+
+/// 根据编辑脚本生成hunks，context为上下文行数
+pub fn build_hunks<S: AsRef<str>>(
+    edits: &[Edit],
+    old: &[S],
+    new: &[S],
+    options: &DiffOptions,
+    context: usize,
+) -> Vec<Hunk> {
+    let is_blank = |edit: &Edit| match edit {
+        Edit::Delete(i) => old[*i].as_ref().trim().is_empty(),
+        Edit::Insert(j) => new[*j].as_ref().trim().is_empty(),
+        Edit::Equal(..) => false,
+    };
+    // 需要展示的更改
+    let seeds: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !(matches!(e, Edit::Equal(..)) || options.ignore_blank_lines && is_blank(e)))
+        .map(|(idx, _)| idx)
+        .collect();
+
+    // 合并相邻的更改区间 [start, end)
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for seed in seeds {
+        let start = seed.saturating_sub(context);
+        let end = (seed + context + 1).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    // 每个编辑之前已消耗的新旧行数
+    let mut consumed = Vec::with_capacity(edits.len());
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for edit in edits {
+        consumed.push((old_pos, new_pos));
+        match edit {
+            Edit::Equal(..) => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            Edit::Delete(_) => old_pos += 1,
+            Edit::Insert(_) => new_pos += 1,
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let mut hunk = Hunk {
+                old_start: 0,
+                old_len: 0,
+                new_start: 0,
+                new_len: 0,
+                lines: Vec::new(),
+            };
+            for edit in &edits[start..end] {
+                match *edit {
+                    Edit::Equal(i, _) => {
+                        hunk.lines.push(DiffLine::Context(old[i].as_ref().to_string()));
+                        hunk.old_len += 1;
+                        hunk.new_len += 1;
+                    }
+                    Edit::Delete(i) => {
+                        hunk.lines.push(DiffLine::Delete(old[i].as_ref().to_string()));
+                        hunk.old_len += 1;
+                    }
+                    Edit::Insert(j) => {
+                        hunk.lines.push(DiffLine::Insert(new[j].as_ref().to_string()));
+                        hunk.new_len += 1;
+                    }
+                }
+            }
+            let (old_before, new_before) = consumed[start];
+            hunk.old_start = if hunk.old_len == 0 { old_before } else { old_before + 1 };
+            hunk.new_start = if hunk.new_len == 0 { new_before } else { new_before + 1 };
+            hunk
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [DiffAlgorithm; 3] = [DiffAlgorithm::Myers, DiffAlgorithm::Patience, DiffAlgorithm::Histogram];
+
+    /// 动态规划求LCS长度，用于校验Myers的最优性
+    fn lcs_len(a: &[&str], b: &[&str]) -> usize {
+        let mut dp = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                dp[i][j] = if a[i] == b[j] {
+                    dp[i + 1][j + 1] + 1
+                } else {
+                    dp[i + 1][j].max(dp[i][j + 1])
+                };
+            }
+        }
+        dp[0][0]
+    }
+
+    /// 校验编辑脚本能从old还原出new
+    fn check_edits(a: &[&str], b: &[&str], edits: &[Edit]) {
+        let (mut i, mut j) = (0, 0);
+        for edit in edits {
+            match *edit {
+                Edit::Equal(x, y) => {
+                    assert_eq!((x, y), (i, j));
+                    assert_eq!(a[x], b[y]);
+                    i += 1;
+                    j += 1;
+                }
+                Edit::Delete(x) => {
+                    assert_eq!(x, i);
+                    i += 1;
+                }
+                Edit::Insert(y) => {
+                    assert_eq!(y, j);
+                    j += 1;
+                }
+            }
+        }
+        assert_eq!((i, j), (a.len(), b.len()));
+    }
+
+    #[test]
+    fn test_algorithms_random() {
+        let words = ["a", "b", "c", "d", "}", ""];
+        for _ in 0..300 {
+            let gen = |len: usize| {
+                (0..len)
+                    .map(|_| words[rand::random::<usize>() % words.len()])
+                    .collect::<Vec<_>>()
+            };
+            let a = gen(rand::random::<usize>() % 20);
+            let b = gen(rand::random::<usize>() % 20);
+            for algorithm in ALGORITHMS {
+                let options = DiffOptions { algorithm, ..Default::default() };
+                let edits = diff_lines(&a, &b, &options);
+                check_edits(&a, &b, &edits);
+                if algorithm == DiffAlgorithm::Myers {
+                    let equal = edits.iter().filter(|e| matches!(e, Edit::Equal(..))).count();
+                    assert_eq!(equal, lcs_len(&a, &b), "myers is not minimal: {:?} {:?}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_patience_anchors() {
+        // Myers会将'}'错误匹配，patience以唯一行为锚点
+        let old = "fn a() {\n  one\n}\n\nfn b() {\n  two\n}\n";
+        let new = "fn a() {\n  one\n}\n\nfn c() {\n  three\n}\n\nfn b() {\n  two\n}\n";
+        let options = DiffOptions { algorithm: DiffAlgorithm::Patience, ..Default::default() };
+        let hunks = diff_hunks(old, new, &options, 0);
+        assert_eq!(hunks.len(), 1);
+        let inserted: Vec<_> = hunks[0].lines.iter().filter(|l| matches!(l, DiffLine::Insert(_))).collect();
+        assert_eq!(inserted.len(), 4);
+        assert_eq!(inserted[0], &DiffLine::Insert("fn c() {".to_string()));
+    }
+
+    #[test]
+    fn test_whitespace_options() {
+        let old = "a  b\nc\n";
+        let new = "a b \n\nc\n";
+        let hunks = diff_hunks(old, new, &DiffOptions::default(), 3);
+        assert_eq!(hunks.len(), 1);
+
+        let options = DiffOptions {
+            ignore_space_change: true,
+            ignore_blank_lines: true,
+            ..Default::default()
+        };
+        assert!(diff_hunks(old, new, &options, 3).is_empty());
+
+        let options = DiffOptions { ignore_all_space: true, ..Default::default() };
+        let hunks = diff_hunks("ab\n", "a b\n", &options, 3);
+        assert!(hunks.is_empty());
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("a\nb\nc\nd\n", "a\nb\nc\nd\n"), 100);
+        assert_eq!(similarity("a\nb\nc\nd\n", "a\nb\nx\ny\n"), 50);
+        assert_eq!(similarity("a\n", "b\n"), 0);
+        assert_eq!(similarity("", ""), 100);
+    }
+
+    #[test]
+    fn test_hunk_header() {
+        let old = (1..=10).map(|i| i.to_string()).collect::<Vec<_>>().join("\n");
+        let new = old.replace("5", "five");
+        let hunks = diff_hunks(&old, &new, &DiffOptions::default(), 3);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].header(), "@@ -2,7 +2,7 @@");
+        let hunks = diff_hunks("", "a\n", &DiffOptions::default(), 3);
+        assert_eq!(hunks[0].header(), "@@ -0,0 +1,1 @@");
+    }
+
+    #[test]
+    fn test_merge3() {
+        let options = DiffOptions::default();
+        let base = "1\n2\n3\n4\n5\n";
+        let clean = |chunks: Vec<MergeChunk>| match chunks.as_slice() {
+            [MergeChunk::Clean(lines)] => lines.join("\n"),
+            _ => panic!("unexpected conflict: {:?}", chunks),
+        };
+        assert_eq!(clean(merge3(base, "one\n2\n3\n4\n5\n", "1\n2\n3\n4\nfive\n", &options)), "one\n2\n3\n4\nfive");
+        assert_eq!(clean(merge3(base, "1\n2\n3\n4\n5\n6\n", "0\n1\n2\n4\n5\n", &options)), "0\n1\n2\n4\n5\n6");
+        assert_eq!(clean(merge3(base, "1\n2\nx\n4\n5\n", "1\n2\nx\n4\n5\n", &options)), "1\n2\nx\n4\n5");
+
+        let chunks = merge3(base, "1\n2\nours\n4\n5\n", "1\n2\ntheirs\n4\n5\n", &options);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(
+            chunks[1],
+            MergeChunk::Conflict {
+                base: vec!["3".to_string()],
+                ours: vec!["ours".to_string()],
+                theirs: vec!["theirs".to_string()],
+            }
+        );
+        assert_eq!(chunks[2], MergeChunk::Clean(vec!["4".to_string(), "5".to_string()]));
+    }
+}
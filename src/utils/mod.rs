@@ -1,5 +1,9 @@
+pub mod attributes;
+pub mod config;
+pub mod diff;
 pub mod path_ext;
 pub use path_ext::PathExt;
+pub mod revision;
 pub mod store;
 pub mod test;
 pub mod util;
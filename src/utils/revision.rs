@@ -0,0 +1,307 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+};
+
+use regex::Regex;
+
+use crate::models::{head, reflog, Commit, Hash, Tag};
+
+use super::{
+    store::Store,
+    util::{self, ObjectType},
+};
+
+/// 解析revision失败的原因
+#[derive(Debug, PartialEq)]
+pub enum RevisionErr {
+    /// 无法识别的名称
+    Unknown(String),
+    /// hash前缀有歧义，附带候选
+    Ambiguous(String, Vec<Hash>),
+    /// 语法错误，或对象类型不符合要求
+    Invalid(String),
+}
+
+impl fmt::Display for RevisionErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevisionErr::Unknown(rev) => {
+                write!(f, "fatal: ambiguous argument '{}': unknown revision", rev)?;
+                let similar: Vec<String> = head::list_local_branches()
+                    .into_iter()
+                    .filter(|b| b.contains(rev) || rev.contains(b))
+                    .collect();
+                if !similar.is_empty() {
+                    write!(f, "\nhint: did you mean one of these?")?;
+                    for branch in similar {
+                        write!(f, "\nhint:   {}", branch)?;
+                    }
+                }
+                Ok(())
+            }
+            RevisionErr::Ambiguous(rev, candidates) => {
+                write!(f, "error: short object ID {} is ambiguous\nhint: The candidates are:", rev)?;
+                for hash in candidates {
+                    let kind = format!("{:?}", util::check_object_type(hash.clone())).to_lowercase();
+                    write!(f, "\nhint:   {} {}", &hash[..7.min(hash.len())], kind)?;
+                }
+                Ok(())
+            }
+            RevisionErr::Invalid(msg) => write!(f, "fatal: {}", msg),
+        }
+    }
+}
+
+/** 解析revision表达式，返回object hash
+<br>支持：
+- `HEAD`、`@`、分支名、tag名、（部分）hash
+- `@{-N}`：第N个之前切换过的分支
+- `<ref>@{N}`：ref的reflog中第N个记录，e.g. `HEAD@{2}`、`master@{1}`，`@{N}`为当前分支
+- `:/regex`：最近的提交信息匹配regex的commit
+- 后缀 `~N`、`^N`、`^{tree}`、`^{commit}`、`^{}`，可以组合，e.g. `main~2^2`
+ */
+pub fn resolve(rev: &str) -> Result<Hash, RevisionErr> {
+    if let Some(pattern) = rev.strip_prefix(":/") {
+        return search_message(pattern);
+    }
+    let (base, suffix) = split_suffix(rev);
+    let mut hash = resolve_base(base)?;
+    let mut rest = suffix;
+    while !rest.is_empty() {
+        if let Some(peel) = rest.strip_prefix("^{") {
+            let end = peel.find('}').ok_or(RevisionErr::Invalid(format!("bad revision '{}'", rev)))?;
+            hash = peel_to(&hash, &peel[..end], rev)?;
+            rest = &peel[end + 1..];
+            continue;
+        }
+        let op = rest.chars().next().unwrap();
+        let invalid = || RevisionErr::Invalid(format!("bad revision '{}'", rev));
+        if op != '~' && op != '^' {
+            return Err(invalid());
+        }
+        let digits: String = rest[1..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        rest = &rest[1 + digits.len()..];
+        let n: usize = if digits.is_empty() {
+            1
+        } else {
+            digits.parse().map_err(|_| invalid())?
+        };
+        hash = peel_to(&hash, "commit", rev)?;
+        match op {
+            '~' => {
+                for _ in 0..n {
+                    hash = nth_parent(&hash, 1, rev)?;
+                }
+            }
+            _ => {
+                if n > 0 {
+                    hash = nth_parent(&hash, n, rev)?;
+                }
+            }
+        }
+    }
+    Ok(hash)
+}
+
+/// 解析revision并确保是commit（会自动peel）
+pub fn resolve_commit(rev: &str) -> Result<Hash, RevisionErr> {
+    let hash = resolve(rev)?;
+    peel_to(&hash, "commit", rev)
+}
+
+/// 解析 `@{-N}` 为分支名（或detached时的commit），用于 `switch -`
+pub fn resolve_previous_branch(rev: &str) -> Option<String> {
+    let n = rev.strip_prefix("@{-")?.strip_suffix('}')?.parse::<usize>().ok()?;
+    head::previous_checkout(n)
+}
+
+/// reflog对应的ref：HEAD、stash、分支名（空为当前分支）
+pub fn reflog_ref(name: &str) -> String {
+    match name {
+        "HEAD" => "HEAD".to_string(),
+        "stash" | "refs/stash" => "refs/stash".to_string(),
+        "" | "@" => match head::current_head() {
+            head::Head::Branch(branch) => format!("refs/heads/{}", branch),
+            head::Head::Detached(_) => "HEAD".to_string(),
+        },
+        _ => format!("refs/heads/{}", name.strip_prefix("refs/heads/").unwrap_or(name)),
+    }
+}
+
+/// 解析 `<ref>@{N}`
+fn resolve_reflog(base: &str) -> Option<Result<Hash, RevisionErr>> {
+    let (name, n) = base.strip_suffix('}')?.split_once("@{")?;
+    let n = n.parse::<usize>().ok()?;
+    let entries = reflog::read(&reflog_ref(name));
+    Some(match entries.get(n) {
+        Some(entry) => Ok(entry.new.clone()),
+        None => Err(RevisionErr::Invalid(format!("log for '{}' only has {} entries", name, entries.len()))),
+    })
+}
+
+/// 在第一个`~`或`^`处拆分，`@{...}`内部的字符不算
+fn split_suffix(rev: &str) -> (&str, &str) {
+    let mut depth = 0;
+    for (i, c) in rev.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '~' | '^' if depth == 0 && i > 0 => return (&rev[..i], &rev[i..]),
+            _ => {}
+        }
+    }
+    (rev, "")
+}
+
+fn resolve_base(base: &str) -> Result<Hash, RevisionErr> {
+    if base == "HEAD" || base == "@" {
+        let commit = head::current_head_commit();
+        return if commit.is_empty() {
+            Err(RevisionErr::Invalid(format!("'{}' does not point to a commit yet", base)))
+        } else {
+            Ok(commit)
+        };
+    }
+    if base.starts_with("@{-") {
+        let name = resolve_previous_branch(base)
+            .ok_or(RevisionErr::Invalid(format!("'{}': not enough checkouts in history", base)))?;
+        return resolve_base(&name);
+    }
+    if let Some(result) = resolve_reflog(base) {
+        return result;
+    }
+    let branch = base.strip_prefix("refs/heads/").unwrap_or(base).to_string();
+    if head::list_local_branches().contains(&branch) {
+        let commit = head::get_branch_head(&branch);
+        if !commit.is_empty() {
+            return Ok(commit);
+        }
+    }
+    let tag = head::get_tag(base.strip_prefix("refs/tags/").unwrap_or(base));
+    if !tag.is_empty() {
+        return Ok(tag);
+    }
+    if base.is_empty() || !base.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(RevisionErr::Unknown(base.to_string()));
+    }
+    let mut candidates = Store::new().search_all(&base.to_lowercase());
+    match candidates.len() {
+        0 => Err(RevisionErr::Unknown(base.to_string())),
+        1 => Ok(candidates.pop().unwrap()),
+        _ => Err(RevisionErr::Ambiguous(base.to_string(), candidates)),
+    }
+}
+
+/// 将对象peel为指定类型：commit | tree | blob | tag | ""(任意非tag类型)；附注标签会被解引用
+fn peel_to(hash: &Hash, kind: &str, rev: &str) -> Result<Hash, RevisionErr> {
+    let object_type = util::check_object_type(hash.clone());
+    if object_type == ObjectType::Tag && kind != "tag" {
+        // 附注标签指向的对象
+        return peel_to(&Tag::load(hash).get_object(), kind, rev);
+    }
+    match (kind, object_type) {
+        ("", ObjectType::Invalid) => Err(RevisionErr::Unknown(rev.to_string())),
+        ("", _)
+        | ("commit", ObjectType::Commit)
+        | ("tree", ObjectType::Tree)
+        | ("blob", ObjectType::Blob)
+        | ("tag", ObjectType::Tag) => Ok(hash.clone()),
+        ("tree", ObjectType::Commit) => Ok(Commit::load(hash).get_tree().get_hash()),
+        ("commit" | "tree" | "blob" | "tag", _) => Err(RevisionErr::Invalid(format!("'{}' is not a {}", rev, kind))),
+        _ => Err(RevisionErr::Invalid(format!("unknown object type '{}' in '{}'", kind, rev))),
+    }
+}
+
+fn nth_parent(hash: &Hash, n: usize, rev: &str) -> Result<Hash, RevisionErr> {
+    Commit::load(hash)
+        .get_parent_hash()
+        .get(n - 1)
+        .cloned()
+        .ok_or(RevisionErr::Invalid(format!("'{}': commit {} has no parent #{}", rev, &hash[..7], n)))
+}
+
+/// 从HEAD和所有分支出发，按提交时间由新到旧查找提交信息匹配的commit
+fn search_message(pattern: &str) -> Result<Hash, RevisionErr> {
+    let regex = Regex::new(pattern).map_err(|e| RevisionErr::Invalid(format!("invalid regex '{}': {}", pattern, e)))?;
+    let mut starts: Vec<Hash> = head::list_local_branches().iter().map(head::get_branch_head).collect();
+    starts.push(head::current_head_commit());
+    let mut visited: HashSet<Hash> = HashSet::new();
+    let mut queue: VecDeque<Hash> = starts.into_iter().filter(|h| !h.is_empty()).collect();
+    let mut matched: Vec<Commit> = Vec::new();
+    while let Some(hash) = queue.pop_front() {
+        if !visited.insert(hash.clone()) {
+            continue;
+        }
+        let commit = Commit::load(&hash);
+        if regex.is_match(&commit.get_message()) {
+            matched.push(commit.clone());
+        }
+        queue.extend(commit.get_parent_hash());
+    }
+    matched
+        .into_iter()
+        .max_by_key(|c| c.get_time())
+        .map(|c| c.get_hash())
+        .ok_or(RevisionErr::Unknown(format!(":/{}", pattern)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commands as cmd, utils::test};
+
+    #[test]
+    fn test_resolve() {
+        test::setup_with_empty_workdir();
+        assert!(matches!(resolve("HEAD"), Err(RevisionErr::Invalid(_))));
+        cmd::commit("first".to_string(), true);
+        let first = head::current_head_commit();
+        cmd::commit("second".to_string(), true);
+        let second = head::current_head_commit();
+        cmd::commit("third".to_string(), true);
+        let third = head::current_head_commit();
+
+        assert_eq!(resolve("HEAD").unwrap(), third);
+        assert_eq!(resolve("@").unwrap(), third);
+        assert_eq!(resolve("master").unwrap(), third);
+        assert_eq!(resolve("HEAD~").unwrap(), second);
+        assert_eq!(resolve("HEAD~2").unwrap(), first);
+        assert_eq!(resolve("master^^").unwrap(), first);
+        assert_eq!(resolve("HEAD~1^1").unwrap(), first);
+        assert_eq!(resolve("HEAD^0").unwrap(), third);
+        assert_eq!(resolve(&third[..7]).unwrap(), third);
+        assert_eq!(resolve("HEAD^{tree}").unwrap(), Commit::load(&third).get_tree().get_hash());
+        assert_eq!(resolve_commit(&format!("{}^{{commit}}", &second[..8])).unwrap(), second);
+        assert_eq!(resolve(":/sec").unwrap(), second);
+        assert_eq!(resolve(":/^(first|third)$").unwrap(), third);
+        assert_eq!(resolve("HEAD@{0}").unwrap(), third);
+        assert_eq!(resolve("HEAD@{2}").unwrap(), first);
+        assert_eq!(resolve("master@{1}~1").unwrap(), first);
+        assert_eq!(resolve("@{1}").unwrap(), second);
+
+        assert!(matches!(resolve("HEAD~3"), Err(RevisionErr::Invalid(_))));
+        assert!(matches!(resolve("HEAD^2"), Err(RevisionErr::Invalid(_))));
+        assert!(matches!(resolve("no-such-branch"), Err(RevisionErr::Unknown(_))));
+        assert!(matches!(resolve(":/nothing"), Err(RevisionErr::Unknown(_))));
+        assert!(matches!(resolve("HEAD@{3}"), Err(RevisionErr::Invalid(_))));
+        assert!(resolve_commit("HEAD^{tree}").is_err());
+        assert!(matches!(resolve("HEAD~99999999999999999999"), Err(RevisionErr::Invalid(_))));
+        assert!(matches!(resolve("HEAD^{tree}é"), Err(RevisionErr::Invalid(_))));
+        assert!(!matches!(resolve(&third[..3]), Err(RevisionErr::Unknown(_)))); // 短前缀：唯一或有歧义
+    }
+
+    #[test]
+    fn test_previous_branch() {
+        test::setup_with_empty_workdir();
+        cmd::commit("first".to_string(), true);
+        cmd::switch(None, Some("dev".to_string()), false);
+        cmd::commit("dev".to_string(), true);
+        assert_eq!(resolve_previous_branch("@{-1}"), Some("master".to_string()));
+        assert_eq!(resolve("@{-1}").unwrap(), head::get_branch_head(&"master".to_string()));
+        cmd::switch(Some("-".to_string()), None, false);
+        assert_eq!(resolve_previous_branch("@{-1}"), Some("dev".to_string()));
+        assert_eq!(resolve_previous_branch("@{-2}"), Some("master".to_string()));
+        assert!(resolve("@{-3}").is_err());
+    }
+}
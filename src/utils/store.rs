@@ -38,26 +38,19 @@ impl Store {
         }
     }
 
-    /** 根据前缀搜索，有歧义时返回 None */
-    pub fn search(&self, hash: &String) -> Option<Hash> {
+    /** 根据前缀搜索所有匹配的object，多于一个时说明有歧义 */
+    pub fn search_all(&self, hash: &String) -> Vec<Hash> {
         if hash.is_empty() {
-            return None;
+            return Vec::new();
         }
         let objects = util::list_files(self.store_path.join("objects").as_path()).unwrap();
         // 转string
-        let objects = objects
+        let mut result = objects
             .iter()
             .map(|x| x.file_name().unwrap().to_str().unwrap().to_string())
+            .filter(|object| object.starts_with(hash))
             .collect::<Vec<String>>();
-        let mut result = None;
-        for object in objects {
-            if object.starts_with(hash) {
-                if result.is_some() {
-                    return None;
-                }
-                result = Some(object);
-            }
-        }
+        result.sort();
         result
     }
 
@@ -119,7 +112,7 @@ mod tests {
     #[test]
     fn test_search() {
         test::setup_with_clean_mit();
-        let hashs = vec!["1234567890".to_string(), "1235467891".to_string(), "4567892".to_string()];
+        let hashs = ["1234567890".to_string(), "1235467891".to_string(), "4567892".to_string()];
         for hash in hashs.iter() {
             let mut path = util::get_storage_path().unwrap();
             path.push("objects");
@@ -127,9 +120,9 @@ mod tests {
             fs::write(path, "hello world").unwrap();
         }
         let store = Store::new();
-        assert!(store.search(&"123".to_string()).is_none()); // 有歧义
-        assert!(store.search(&"1234".to_string()).is_some()); // 精确
-        assert!(store.search(&"4".to_string()).is_some()); // 精确
-        assert!(store.search(&"1234567890123".to_string()).is_none()); // 不匹配
+        assert_eq!(store.search_all(&"123".to_string()).len(), 2); // 有歧义
+        assert_eq!(store.search_all(&"1234".to_string()).len(), 1); // 精确
+        assert_eq!(store.search_all(&"4".to_string()).len(), 1); // 精确
+        assert!(store.search_all(&"1234567890123".to_string()).is_empty()); // 不匹配
     }
 }
@@ -7,7 +7,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::models::Index;
+use crate::models::{head, Hash, Index};
 use crate::utils::PathExt;
 
 // 执行测试的储存库
@@ -48,7 +48,7 @@ fn setup_env() {
 }
 
 pub fn init_mit() {
-    let _ = crate::commands::init();
+    let _ = crate::commands::init(Default::default());
     Index::reload(); // 重置index, 以防止其他测试修改了index单例
 }
 
@@ -113,6 +113,14 @@ pub fn ensure_no_file(path: &Path) {
     }
 }
 
+/// 写入文件并提交所有更改，返回新的commit
+pub fn write_and_commit(path: &str, content: &str, message: &str) -> Hash {
+    ensure_file(Path::new(path), Some(content));
+    crate::commands::add(vec![], true, false);
+    crate::commands::commit(message.to_string(), false);
+    head::current_head_commit()
+}
+
 /** 列出子文件夹 */
 pub fn list_subdir(path: &Path) -> io::Result<Vec<PathBuf>> {
     let mut files = Vec::new();
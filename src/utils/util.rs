@@ -2,9 +2,11 @@ use std::{
     collections::HashSet,
     fs, io,
     path::{Path, PathBuf},
+    sync::Mutex,
 };
 
-use crate::models::{commit::Commit, object::Hash, tree::Tree};
+use super::config::Config;
+use crate::models::{commit::Commit, object::Hash, tag::Tag, tree::Tree};
 
 pub const ROOT_DIR: &str = ".mit";
 
@@ -22,9 +24,85 @@ pub fn check_repo_exist() {
         panic!("不是合法的mit仓库");
     }
 }
+
+/** 检查是否有工作区，裸仓库中打印错误并返回false；需要工作区的命令（add、status等）应先检查
+<br>e.g. `if !util::check_worktree_exist() { return; }`
+ */
+pub fn check_worktree_exist() -> bool {
+    if is_bare() {
+        println!("fatal: this operation must be run in a work tree");
+        return false;
+    }
+    true
+}
+
+/// dir是否为仓库目录（.mit或裸仓库）：dir下直接有HEAD、objects、refs
+fn is_repo_dir(dir: &Path) -> bool {
+    dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir()
+}
+
+/// 当前仓库是否为裸仓库（`init --bare`，配置core.bare），没有工作区
+pub fn is_bare() -> bool {
+    storage_exist() && get_working_dir().is_none()
+}
+
+/// 指定仓库目录的环境变量，等价于 `mit --mit-dir <path>`
+pub const MIT_DIR: &str = "MIT_DIR";
+/// 指定工作区目录的环境变量，等价于 `mit --work-tree <path>`
+pub const MIT_WORK_TREE: &str = "MIT_WORK_TREE";
+
+/// 读取路径类型的环境变量，未设置或为空时为None
+pub fn env_path(key: &str) -> Option<PathBuf> {
+    std::env::var_os(key)
+        .filter(|value| !value.is_empty())
+        .map(|value| get_absolute_path(Path::new(&value)))
+}
+
+/** 设置仓库位置：命令行参数优先于环境变量，统一转换为绝对路径后写回环境变量
+<br>指定了工作区且当前目录不在其中时，切换到工作区根目录，路径参数相对于工作区根目录解析；
+只指定仓库时当前目录即为工作区，同git
+<br>init时仓库还不存在：不检查仓库和工作区，也不切换目录，由init在MIT_DIR处创建仓库
+ */
+pub fn setup_repo_location(mit_dir: Option<String>, work_tree: Option<String>, init: bool) -> io::Result<()> {
+    for (key, arg) in [(MIT_DIR, mit_dir), (MIT_WORK_TREE, work_tree)] {
+        if let Some(path) = arg.map(|path| get_absolute_path(Path::new(&path))).or_else(|| env_path(key)) {
+            std::env::set_var(key, path);
+        }
+    }
+    if init {
+        return Ok(());
+    }
+    if let Some(mit_dir) = env_path(MIT_DIR) {
+        if !is_repo_dir(&mit_dir) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("not a mit repository: '{}'", mit_dir.display()),
+            ));
+        }
+    }
+    if let Some(work_tree) = env_path(MIT_WORK_TREE) {
+        if !work_tree.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("work tree '{}' does not exist", work_tree.display()),
+            ));
+        }
+        if !cur_dir().starts_with(&work_tree) {
+            std::env::set_current_dir(&work_tree)?;
+        }
+    }
+    Ok(())
+}
+
 // TODO 拆分为PathExt.rs，并定义为trait，实现PathBuf的扩展方法
-/// 获取.mit目录路径
+/// 获取.mit目录路径；裸仓库时为仓库目录本身，设置了 MIT_DIR 时为该目录
 pub fn get_storage_path() -> Result<PathBuf, io::Error> {
+    if let Some(mit_dir) = env_path(MIT_DIR) {
+        if !is_repo_dir(&mit_dir) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{:?} is not a mit repository", mit_dir)));
+        }
+        return Ok(mit_dir);
+    }
     /*递归获取储存库 */
     let mut current_dir = std::env::current_dir()?;
     loop {
@@ -33,18 +111,57 @@ pub fn get_storage_path() -> Result<PathBuf, io::Error> {
         if git_path.exists() {
             return Ok(git_path);
         }
+        if is_repo_dir(&current_dir) {
+            return Ok(current_dir);
+        }
         if !current_dir.pop() {
             return Err(io::Error::new(
                 io::ErrorKind::NotFound,
-                format!("{:?} is not a git repository", std::env::current_dir()?),
+                format!("{:?} is not a mit repository", std::env::current_dir()?),
             ));
         }
     }
 }
 
-/// 获取项目工作区目录, 也就是.mit的父目录
+/** 获取项目工作区目录, 也就是.mit的父目录；裸仓库（core.bare）没有工作区
+<br>设置了 MIT_WORK_TREE 时为该目录；只设置了 MIT_DIR 时为当前目录
+ */
 pub fn get_working_dir() -> Option<PathBuf> {
-    get_storage_path().unwrap().parent().map(|path| path.to_path_buf())
+    if let Some(work_tree) = env_path(MIT_WORK_TREE) {
+        return Some(work_tree);
+    }
+    let storage = get_storage_path().unwrap();
+    if is_bare_storage(&storage) {
+        return None;
+    }
+    if env_path(MIT_DIR).is_some() {
+        return Some(cur_dir()); // 只指定了仓库：当前目录为工作区
+    }
+    storage.parent().map(|path| path.to_path_buf())
+}
+
+/// 缓存的仓库类型：(仓库目录, 是否为裸仓库)；切换仓库时重新判断，保存配置时清除
+static BARE_CACHE: Mutex<Option<(PathBuf, bool)>> = Mutex::new(None);
+
+/// 仓库是否为裸仓库：根据core.bare，没有core.bare时按目录名判断；每个仓库只读取一次配置
+fn is_bare_storage(storage: &Path) -> bool {
+    let mut cache = BARE_CACHE.lock().unwrap();
+    if let Some((path, bare)) = cache.as_ref() {
+        if path == storage {
+            return *bare;
+        }
+    }
+    let bare = match Config::load_from(storage.join("config")).get("core.bare") {
+        Some(bare) => bare == "true",
+        None => storage.file_name().unwrap_or_default() != ROOT_DIR,
+    };
+    *cache = Some((storage.to_path_buf(), bare));
+    bare
+}
+
+/// 清除裸仓库的缓存，配置（可能是core.bare）改变后调用
+pub fn clear_bare_cache() {
+    *BARE_CACHE.lock().unwrap() = None;
 }
 
 /// 检查文件是否在dir内(包括子文件夹)， 若不存在则false
@@ -129,12 +246,17 @@ pub fn format_time(time: &std::time::SystemTime) -> String {
     datetime.format("%Y-%m-%d %H:%M:%S.%3f").to_string()
 }
 
+/// 当前unix时间戳(秒)
+pub fn timestamp() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
+}
+
 /// 递归遍历给定目录及其子目录，列出所有文件，除了.mit
 pub fn list_files(path: &Path) -> io::Result<Vec<PathBuf>> {
     let mut files = Vec::new();
     if path.is_dir() {
-        if path.file_name().unwrap_or_default() == ROOT_DIR {
-            // 跳过 .mit 目录
+        if path.file_name().unwrap_or_default() == ROOT_DIR || env_path(MIT_DIR) == Some(get_absolute_path(path)) {
+            // 跳过 .mit 目录，以及 --mit-dir 指定的仓库目录
             return Ok(files);
         }
         for entry in fs::read_dir(path)? {
@@ -203,11 +325,7 @@ pub fn cur_dir() -> PathBuf {
 
 /// 列出工作区所有文件(包括子文件夹)
 pub fn list_workdir_files() -> Vec<PathBuf> {
-    if let Ok(files) = list_files(&get_working_dir().unwrap()) {
-        files
-    } else {
-        Vec::new()
-    }
+    list_files(&get_working_dir().unwrap()).unwrap_or_default()
 }
 
 /// 获取相对于dir的 规范化 相对路径（不包含../ ./）
@@ -310,9 +428,7 @@ pub fn get_absolute_path_to_dir(path: &Path, dir: &Path) -> PathBuf {
         for component in path.components() {
             match component {
                 std::path::Component::ParentDir => {
-                    if !abs_path.pop() {
-                        panic!("relative path parse error");
-                    }
+                    assert!(abs_path.pop(), "relative path parse error");
                 }
                 std::path::Component::Normal(part) => abs_path.push(part),
                 std::path::Component::CurDir => {}
@@ -343,6 +459,7 @@ pub enum ObjectType {
     Blob,
     Tree,
     Commit,
+    Tag,
     Invalid,
 }
 pub fn check_object_type(hash: Hash) -> ObjectType {
@@ -357,16 +474,15 @@ pub fn check_object_type(hash: Hash) -> ObjectType {
         if result.is_ok() {
             return ObjectType::Tree;
         }
+        let result: Result<Tag, serde_json::Error> = serde_json::from_str(&data);
+        if result.is_ok() {
+            return ObjectType::Tag;
+        }
         return ObjectType::Blob;
     }
     ObjectType::Invalid
 }
 
-/// 判断hash对应的文件是否是commit
-pub fn is_typeof_commit(hash: Hash) -> bool {
-    check_object_type(hash) == ObjectType::Commit
-}
-
 /// 将内容对应的文件内容(主要是blob)还原到file
 pub fn write_workfile(content: String, file: &PathBuf) {
     let mut parent = file.clone();
@@ -508,4 +624,52 @@ mod tests {
         fs::create_dir("./src/.mit").unwrap_or_default();
         assert!(include_root_dir(Path::new("./src")));
     }
+
+    #[test]
+    fn test_setup_repo_location() {
+        test::setup_with_clean_mit();
+        let storage = get_storage_path().unwrap();
+        let work_dir = get_working_dir().unwrap();
+        let store = work_dir.join("other").join("store");
+        fs::create_dir_all(&store).unwrap();
+
+        // --mit-dir：当前目录为工作区；仓库目录不叫 .mit 时也不是裸仓库
+        fs::create_dir_all(store.join("refs/heads")).unwrap();
+        fs::create_dir_all(store.join("objects")).unwrap();
+        fs::copy(storage.join("HEAD"), store.join("HEAD")).unwrap();
+        fs::copy(storage.join("config"), store.join("config")).unwrap();
+        std::env::set_current_dir(work_dir.join("other")).unwrap();
+        setup_repo_location(Some("store".to_string()), None, false).unwrap();
+        assert_eq!(get_storage_path().unwrap(), store);
+        assert_eq!(get_working_dir().unwrap(), work_dir.join("other"));
+        assert_eq!(cur_dir(), work_dir.join("other"));
+        assert!(!is_bare());
+        assert!(list_workdir_files().is_empty());
+        std::env::remove_var(MIT_DIR);
+
+        // --work-tree：当前目录不在工作区内时切换到工作区
+        std::env::set_current_dir(work_dir.parent().unwrap()).unwrap();
+        let options = (Some(storage.to_str().unwrap().to_string()), Some(work_dir.to_str().unwrap().to_string()));
+        setup_repo_location(options.0, options.1, false).unwrap();
+        assert_eq!(get_storage_path().unwrap(), storage);
+        assert_eq!(get_working_dir().unwrap(), work_dir);
+        assert_eq!(cur_dir(), work_dir);
+        assert!(setup_repo_location(None, Some("nothing".to_string()), false).is_err());
+        std::env::remove_var(MIT_WORK_TREE);
+
+        // init：仓库还不存在，也不切换到工作区
+        std::env::set_current_dir(work_dir.parent().unwrap()).unwrap();
+        setup_repo_location(Some("new-store".to_string()), Some(work_dir.to_str().unwrap().to_string()), true).unwrap();
+        assert_eq!(env_path(MIT_DIR), Some(work_dir.parent().unwrap().join("new-store")));
+        assert_eq!(cur_dir(), work_dir.parent().unwrap());
+        std::env::remove_var(MIT_WORK_TREE);
+        std::env::remove_var(MIT_DIR);
+
+        // 不存在的仓库
+        assert!(setup_repo_location(Some("nowhere".to_string()), None, false).is_err());
+        assert!(!storage_exist());
+
+        std::env::remove_var(MIT_DIR);
+        std::env::set_current_dir(&work_dir).unwrap();
+    }
 }